@@ -19,7 +19,7 @@ struct Cli {
     /// 問題ファイル。
     path_problem: PathBuf,
 
-    /// 解ファイル。
+    /// 解ファイル (投げる行のカンマ区切り、または着手記法 "r9>F3,r2vA1" など)。
     path_solution: PathBuf,
 }
 
@@ -30,6 +30,12 @@ enum Format {
 
     /// Neshawk の TAStudio にペーストできるムービーを出力する。
     Neshawk,
+
+    /// 着手ごとに、全ての合法手の最適な続きのコストと、実際の着手による損失 (regret) を出力する。
+    Explain,
+
+    /// 解を着手記法 (投げる行、移動方向、最初に当たるマス) で出力する。
+    Notation,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -43,14 +49,6 @@ fn main() -> anyhow::Result<()> {
     })?;
     let problem: Problem = problem.parse()?;
 
-    let solution = std::fs::read_to_string(&cli.path_solution).with_context(|| {
-        format!(
-            "解ファイル '{}' を読み取れない",
-            cli.path_solution.display()
-        )
-    })?;
-    let solution: Solution = solution.trim_end().parse()?; // 改行があるとパースに失敗する。
-
     let config = SolverConfig {
         last_stage: cli.last_stage,
         ..Default::default()
@@ -58,9 +56,21 @@ fn main() -> anyhow::Result<()> {
 
     let solver_arg = SolverArgument::new(&problem, &config);
 
+    let solution = std::fs::read_to_string(&cli.path_solution).with_context(|| {
+        format!(
+            "解ファイル '{}' を読み取れない",
+            cli.path_solution.display()
+        )
+    })?;
+    // 改行があるとパースに失敗する。
+    let solution = parse_move_notation(&solver_arg, solution.trim_end())?;
+    let solution = Solution::from_moves(&solution);
+
     match cli.format {
         Format::Pretty => format_pretty(&solver_arg, &solution),
         Format::Neshawk => format_neshawk(&solver_arg, &solution),
+        Format::Explain => format_explain(&solver_arg, &solution)?,
+        Format::Notation => println!("{}", solution.to_notation(&solver_arg)?),
     }
 
     Ok(())
@@ -86,7 +96,7 @@ fn format_pretty(solver_arg: &SolverArgument, solution: &Solution) {
             .find(|mv| mv.src() == src)
             .unwrap_or_else(|| panic!("{i} 番目の着手が不正: {src:?}"));
         assert!(pos.is_legal_move(mv), "{i} 番目の着手が不正: {mv:?}");
-        let (pos_nxt, cost_hero_move, cost_throw, _erase_count) = pos.do_move(mv);
+        let (pos_nxt, cost_hero_move, cost_throw, _erase_count, _sq_last) = pos.do_move(mv);
 
         pos = pos_nxt;
         moves = pos.update_moves(&moves);
@@ -117,6 +127,37 @@ fn format_pretty(solver_arg: &SolverArgument, solution: &Solution) {
     println!("総コスト: {cost_total}");
 }
 
+fn format_explain(solver_arg: &SolverArgument, solution: &Solution) -> anyhow::Result<()> {
+    let explanations = explain_solution(solver_arg, solution)?;
+
+    for (i, explanation) in explanations.iter().enumerate() {
+        let alternatives = explanation
+            .alternatives
+            .iter()
+            .map(|&(src, cost)| match cost {
+                Some(cost) => format!("{}:{cost}", src.to_inner()),
+                None => format!("{}:-", src.to_inner()),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mark = if explanation.is_exact {
+            ""
+        } else {
+            " (探索打ち切り)"
+        };
+        println!(
+            "着手 {i}: {} (regret={}){mark} [{alternatives}]",
+            explanation.played.to_inner(),
+            explanation.regret()
+        );
+    }
+
+    let regret_total: Cost = explanations.iter().map(MoveExplanation::regret).sum();
+    println!("総 regret: {regret_total}");
+
+    Ok(())
+}
+
 fn format_neshawk(solver_arg: &SolverArgument, solution: &Solution) {
     let movie = solution_to_movie(solver_arg, solution);
 
@@ -140,7 +181,7 @@ fn solution_to_movie(solver_arg: &SolverArgument, solution: &Solution) -> Vec<Mo
 
         let mv = moves.iter().copied().find(|mv| mv.src() == src).unwrap();
         assert!(pos.is_legal_move(mv));
-        let (pos_nxt, _cost_hero_move, cost_throw, _erase_count) = pos.do_move(mv);
+        let (pos_nxt, _cost_hero_move, cost_throw, _erase_count, _sq_last) = pos.do_move(mv);
 
         // ブロックを投げて待つ。
         // ムービーを出力する分には最終手のコスト調整は特に不要。
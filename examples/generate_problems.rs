@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context as _};
+use clap::Parser;
+use log::info;
+
+use flipull_solver::*;
+
+/// シードから決定的にランダムな問題を生成し、`NN.in` (解ける問題のみの場合は `NN.out` も) として出力する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// 乱数シード。
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// ADVANCE モードの問題を、この手数制限で生成する (省略時は NORMAL モード)。
+    #[arg(long)]
+    advance: Option<u8>,
+
+    /// NORMAL モードのブロック規定数。
+    #[arg(long, default_value_t = 5)]
+    target: u8,
+
+    /// NORMAL モードの盤面にワイルドカードを配置するかどうか。
+    #[arg(long)]
+    wild: bool,
+
+    /// 地形をこの問題ファイルから取る。
+    #[arg(long)]
+    ground: Option<PathBuf>,
+
+    /// 解ける問題のみを出力する (1 問あたりの試行回数の上限)。
+    #[arg(long)]
+    solvable: Option<usize>,
+
+    /// 出力先ディレクトリ。
+    dir_out: PathBuf,
+
+    /// 生成する問題数。
+    count: usize,
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+
+    let cli = Cli::parse();
+
+    let mode = match cli.advance {
+        Some(move_count_remain) => GeneratorMode::Advance { move_count_remain },
+        None => GeneratorMode::Normal {
+            block_count_target: cli.target,
+            wild: cli.wild,
+        },
+    };
+
+    let mut generator = ProblemGenerator::new(cli.seed, mode);
+    if let Some(path) = &cli.ground {
+        let problem = std::fs::read_to_string(path)
+            .with_context(|| format!("問題ファイル '{}' を読み取れない", path.display()))?;
+        let problem: Problem = problem.parse()?;
+        generator = generator.with_ground(problem.ground().clone());
+    }
+
+    std::fs::create_dir_all(&cli.dir_out).with_context(|| {
+        format!(
+            "出力先ディレクトリ '{}' を作成できない",
+            cli.dir_out.display()
+        )
+    })?;
+
+    let config = SolverConfig::default();
+
+    for i in 0..cli.count {
+        let path_in = cli.dir_out.join(format!("{i:02}.in"));
+
+        let problem = match cli.solvable {
+            Some(attempt_max) => {
+                let (problem, solution, cost) = generator
+                    .generate_solvable(&config, attempt_max)
+                    .ok_or_else(|| {
+                    anyhow!("{attempt_max} 回の試行で解ける問題が得られなかった")
+                })?;
+                info!("{}: cost={cost}", path_in.display());
+                let path_out = cli.dir_out.join(format!("{i:02}.out"));
+                std::fs::write(&path_out, format!("{solution}\n"))?;
+                problem
+            }
+            None => generator.generate(),
+        };
+
+        std::fs::write(&path_in, problem.to_string())?;
+    }
+
+    Ok(())
+}
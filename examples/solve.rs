@@ -1,11 +1,63 @@
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use anyhow::{ensure, Context as _};
-use clap::Parser;
-use log::info;
+use anyhow::{anyhow, ensure, Context as _};
+use clap::{Parser, ValueEnum};
+use log::{info, warn};
 
 use flipull_solver::*;
 
+/// 着手を試す順序 (`MoveOrdering` に対応)。
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum CliMoveOrdering {
+    Static,
+    EraseCount,
+    HeroTravel,
+    History,
+}
+
+impl From<CliMoveOrdering> for MoveOrdering {
+    fn from(ordering: CliMoveOrdering) -> Self {
+        match ordering {
+            CliMoveOrdering::Static => Self::Static,
+            CliMoveOrdering::EraseCount => Self::EraseCount,
+            CliMoveOrdering::HeroTravel => Self::HeroTravel,
+            CliMoveOrdering::History => Self::History,
+        }
+    }
+}
+
+/// 解の評価指標 (`Metric` に対応)。
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum CliMetric {
+    Cost,
+    MoveCount,
+    BlockCount,
+}
+
+impl From<CliMetric> for Metric {
+    fn from(metric: CliMetric) -> Self {
+        match metric {
+            CliMetric::Cost => Self::Cost,
+            CliMetric::MoveCount => Self::MoveCount,
+            CliMetric::BlockCount => Self::BlockCount,
+        }
+    }
+}
+
+/// 探索の進捗をログ出力する。
+#[derive(Debug)]
+struct ProgressLogger;
+
+impl SolverObserver for ProgressLogger {
+    fn on_progress(&self, stats: &SolverStats) {
+        info!(
+            "progress: {} nodes, depth {}, {:.1?}",
+            stats.node_count, stats.depth_max, stats.elapsed
+        );
+    }
+}
+
 /// 与えられた問題に対する実時間最速の解を求める。
 #[derive(Debug, Parser)]
 struct Cli {
@@ -21,6 +73,83 @@ struct Cli {
     #[arg(long)]
     forbid_just: bool,
 
+    /// 最終局面の残りブロック数。
+    #[arg(long)]
+    end_block_count: Option<u8>,
+
+    /// パーフェクトを要求するかどうか。
+    #[arg(long)]
+    perfect: bool,
+
+    /// 最終局面の保持ブロック。
+    #[arg(long)]
+    end_block_holding: Option<u8>,
+
+    /// 最終局面の自機位置。
+    #[arg(long)]
+    end_hero_row: Option<u8>,
+
+    /// 着手数の最小値。
+    #[arg(long)]
+    min_moves: Option<u8>,
+
+    /// 着手数の最大値。
+    #[arg(long)]
+    max_moves: Option<u8>,
+
+    /// IDA* で探索するかどうか。
+    #[arg(long)]
+    ida_star: bool,
+
+    /// 指定した場合、この幅でビームサーチする (最適解とは限らない)。
+    #[arg(long, conflicts_with = "ida_star")]
+    beam: Option<usize>,
+
+    /// 着手を試す順序。
+    #[arg(long, value_enum, default_value_t = CliMoveOrdering::Static)]
+    move_ordering: CliMoveOrdering,
+
+    /// 探索スレッド数 (`parallel` feature が有効な場合のみ意味を持つ)。
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+
+    /// 指定した場合、コストが小さい順に最大でこの個数の解を求める。
+    #[arg(long)]
+    top_k: Option<usize>,
+
+    /// 指定した場合、コストがこの値以下の全ての解を列挙する。
+    #[arg(long, conflicts_with = "top_k")]
+    within: Option<u16>,
+
+    /// 指定した場合、これらの評価指標 (カンマ区切り) についてパレート最適な解を全て求める。
+    #[arg(long, value_enum, value_delimiter = ',', conflicts_with_all = ["top_k", "within"])]
+    pareto: Vec<CliMetric>,
+
+    /// 解が見つからなかった場合、代わりに残りブロック数が最小となる手順を求める。
+    #[arg(long)]
+    fewest_blocks: bool,
+
+    /// 探索するノード数の上限。
+    #[arg(long, default_value_t = u64::MAX)]
+    max_nodes: u64,
+
+    /// 探索時間の上限 (秒)。
+    #[arg(long)]
+    time_limit: Option<f64>,
+
+    /// 探索の進捗をログ出力するかどうか。
+    #[arg(long)]
+    progress: bool,
+
+    /// 局面ファイル。指定した場合、問題の初期局面の代わりにこの局面から解く
+    /// (地形とゲームモードは問題ファイルのものを使う)。
+    #[arg(long)]
+    position: Option<PathBuf>,
+
+    /// 指定した場合、この手順 (カンマ区切り) を指した後の最適な続きを求める。
+    #[arg(long)]
+    prefix: Option<String>,
+
     /// 問題ファイル。
     path_problem: PathBuf,
 }
@@ -38,15 +167,114 @@ fn main() -> anyhow::Result<()> {
     })?;
     let problem: Problem = problem.parse()?;
 
+    let end_constraints = EndConstraints {
+        block_count: cli.end_block_count,
+        perfect: cli.perfect,
+        block_holding: cli
+            .end_block_holding
+            .map(|inner| {
+                Block::from_inner(inner).ok_or_else(|| anyhow!("無効な保持ブロック値: {inner}"))
+            })
+            .transpose()?,
+        hero_row: cli
+            .end_hero_row
+            .map(|inner| {
+                GroundRow::from_inner(inner).ok_or_else(|| anyhow!("無効な自機位置: {inner}"))
+            })
+            .transpose()?,
+        move_count_min: cli.min_moves,
+        move_count_max: cli.max_moves,
+    };
+
     let config = SolverConfig {
         last_stage: cli.last_stage,
         forbid_five: cli.forbid_five,
         forbid_just: cli.forbid_just,
+        end_constraints,
+        search_mode: if let Some(width) = cli.beam {
+            SearchMode::Beam { width }
+        } else if cli.ida_star {
+            SearchMode::IdaStar
+        } else {
+            SearchMode::DepthFirst
+        },
+        move_ordering: cli.move_ordering.into(),
+        thread_count: cli.threads,
+        max_nodes: cli.max_nodes,
+        time_limit: cli.time_limit.map(std::time::Duration::from_secs_f64),
         ..Default::default()
     };
 
-    let solver_arg = SolverArgument::new(&problem, &config);
-    if let Some((solution, cost)) = solve_problem(&solver_arg) {
+    let mut solver_arg = if let Some(path_position) = &cli.position {
+        let pos = std::fs::read_to_string(path_position).with_context(|| {
+            format!("局面ファイル '{}' を読み取れない", path_position.display())
+        })?;
+        let pos: Position = pos.parse()?;
+        SolverArgument::from_position(problem.ground(), problem.game_mode(), pos, &config)
+    } else {
+        SolverArgument::new(&problem, &config)
+    };
+    if cli.progress {
+        solver_arg = solver_arg.with_observer(Arc::new(ProgressLogger));
+    }
+
+    if let Some(k) = cli.top_k {
+        let solutions = solve_problem_top_k(&solver_arg, k);
+        if solutions.is_empty() {
+            info!("NO SOLUTION FOUND");
+        }
+        for (solution, cost) in solutions {
+            println!("{cost} {solution}");
+        }
+        return Ok(());
+    }
+
+    if !cli.pareto.is_empty() {
+        let metrics: Vec<Metric> = cli.pareto.iter().copied().map(Into::into).collect();
+        let outcome = solve_problem_pareto(&solver_arg, &metrics);
+        info!("{:?}", outcome.stats);
+        if let SolveStatus::Interrupted(reason) = outcome.status {
+            warn!("探索が打ち切られた ({reason:?})。パレート最適とは限らない");
+        }
+        if outcome.front.is_empty() {
+            info!("NO SOLUTION FOUND");
+        }
+        for (solution, metrics) in outcome.front {
+            let SolutionMetrics {
+                cost,
+                move_count,
+                block_count,
+                ..
+            } = metrics;
+            println!("cost={cost} moves={move_count} blocks={block_count} {solution}");
+        }
+        return Ok(());
+    }
+
+    if let Some(max_cost) = cli.within {
+        let mut iter = solutions_within(&solver_arg, max_cost);
+        for (solution, cost) in iter.by_ref() {
+            println!("{cost} {solution}");
+        }
+        info!("{:?}", iter.stats());
+        if let SolveStatus::Interrupted(reason) = iter.status() {
+            warn!("列挙が打ち切られた ({reason:?})。全ての解とは限らない");
+        }
+        return Ok(());
+    }
+
+    let outcome = if let Some(prefix) = &cli.prefix {
+        let prefix: Solution = prefix.parse()?;
+        solve_problem_after_prefix(&solver_arg, &prefix)?
+    } else {
+        solve_problem_outcome(&solver_arg)
+    };
+    info!("{:?}", outcome.stats);
+    if let SolveStatus::Interrupted(reason) = outcome.status {
+        warn!("探索が打ち切られた ({reason:?})。最適解とは限らない");
+    }
+
+    if let Some((solution, cost)) = outcome.best {
         println!("{solution}");
 
         let cost_verify = solution
@@ -58,6 +286,19 @@ fn main() -> anyhow::Result<()> {
         );
     } else {
         info!("NO SOLUTION FOUND");
+
+        if cli.fewest_blocks {
+            let outcome = solve_problem_fewest_blocks(&solver_arg);
+            info!("{:?}", outcome.stats);
+            if let SolveStatus::Interrupted(reason) = outcome.status {
+                warn!("探索が打ち切られた ({reason:?})。最善とは限らない");
+            }
+            if let Some(best) = outcome.best {
+                info!("fewest blocks: {} (cost: {})", best.block_count, best.cost);
+                println!("{}", best.solution);
+                println!("{}", best.position);
+            }
+        }
     }
 
     Ok(())
@@ -50,6 +50,7 @@ fn main() -> anyhow::Result<()> {
         forbid_five: cli.forbid_five,
         forbid_just: cli.forbid_just,
         max_cost: cli.max_cost,
+        ..Default::default()
     };
 
     let mut bests = BoundedHeap::<HeapElement>::new(cli.k);
@@ -104,7 +105,7 @@ fn generate_problems(
     rom: &Rom,
     stage: u8,
     wild: bool,
-) -> impl Iterator<Item = (u16, Problem)> + ExactSizeIterator + std::iter::FusedIterator + '_ {
+) -> impl ExactSizeIterator<Item = (u16, Problem)> + std::iter::FusedIterator + '_ {
     let ground = extract_normal_ground(rom, stage);
     let block_count_target = extract_normal_block_count_target(rom, stage);
 
@@ -332,9 +332,40 @@ impl BlocksSquare {
     }
 }
 
+/// "A1" のように、列の英字と行の数字で表す。
+impl std::fmt::Display for BlocksSquare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let col = char::from(b'A' + self.col().to_inner() - 1);
+        let row = self.row().to_inner();
+        write!(f, "{col}{row}")
+    }
+}
+
+impl std::str::FromStr for BlocksSquare {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let &[col, row] = s.as_bytes() else {
+            return Err(anyhow!("無効なマス: '{s}'"));
+        };
+
+        let col = col
+            .checked_sub(b'A' - 1)
+            .and_then(BlocksCol::from_inner)
+            .ok_or_else(|| anyhow!("無効なマスの列: '{s}'"))?;
+        let row = row
+            .checked_sub(b'0')
+            .and_then(BlocksRow::from_inner)
+            .ok_or_else(|| anyhow!("無効なマスの行: '{s}'"))?;
+
+        Ok(Self::new(col, row))
+    }
+}
+
 /// 盤面左下 6x6 のブロック領域。
 ///
-/// 上下左右に番兵を設けている(内部配列を 8x8 にする意味もある):
+/// マスの値 (`Block` の内部値、空なら 0) の各ビットを、8x8 の 64 bit 盤面 (ビットプレーン) として持つ。
+/// 各マスのビット位置は `BlocksSquare` の内部値で、上下左右の番兵位置のビットは常に 0 とする:
 ///
 /// ```text
 ///    ABCDEF
@@ -347,9 +378,12 @@ impl BlocksSquare {
 /// 6 #......#
 ///   ########
 /// ```
-#[repr(transparent)]
+///
+/// ビットプレーンに直接書き込めないので `IndexMut` は実装しない。書き込みには `set()` を用いる。
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Blocks([Option<Block>; 8 * 8]);
+pub struct Blocks {
+    planes: [u64; 3],
+}
 
 impl Default for Blocks {
     fn default() -> Self {
@@ -365,27 +399,108 @@ impl Blocks {
     const CHAR_NORMAL_4: char = '4';
     const CHAR_WILD: char = '5';
 
+    /// `Index` 用の、マスの値に対応するブロック。
+    const BLOCKS_BY_VALUE: [Option<Block>; 8] = [
+        None,
+        Some(Block::Normal1),
+        Some(Block::Normal2),
+        Some(Block::Normal3),
+        Some(Block::Normal4),
+        Some(Block::Wild),
+        None,
+        None,
+    ];
+
+    /// 1 行ぶん (A-F 列) のマスク。
+    const MASK_ROW: u64 = 0x7E;
+
+    /// 1 列ぶん (1-6 行) のマスク。
+    const MASK_COL: u64 = 0x0001_0101_0101_0100;
+
     pub fn new() -> Self {
-        Self([None; 8 * 8])
+        Self { planes: [0; 3] }
+    }
+
+    /// 盤面を 8x8 の配列に変換して返す (番兵位置は `None`)。
+    ///
+    /// 盤面はビットプレーンで保持しているので、配列への参照ではなくコピーを返す。
+    pub fn to_array(&self) -> [Option<Block>; 8 * 8] {
+        std::array::from_fn(|i| Self::BLOCKS_BY_VALUE[Self::value(&self.planes, i as u32)])
+    }
+
+    /// マス `sq` のブロックを返す。
+    pub fn get(&self, sq: BlocksSquare) -> Option<Block> {
+        self[sq]
     }
 
-    pub fn as_array(&self) -> &[Option<Block>; 8 * 8] {
-        &self.0
+    /// マス `sq` のブロックを `block` にする。
+    pub fn set(&mut self, sq: BlocksSquare, block: Option<Block>) {
+        Self::write_planes(&mut self.planes, sq, block);
     }
 
     pub fn block_count(&self) -> u8 {
-        self.0.iter().copied().filter(Option::is_some).count() as u8
+        self.occupied().count_ones() as u8
+    }
+
+    /// 盤面を 1 マスあたり 3 bit に詰めた値を返す (108 bit を使う)。
+    /// 盤面が異なれば値も異なる。
+    pub fn to_packed(&self) -> u128 {
+        // 各ビットプレーンの有効な 36 bit を詰める。
+        fn compress(plane: u64) -> u128 {
+            BlocksRow::all().into_iter().fold(0, |acc, row| {
+                let bits = (plane >> (8 * row.to_inner() + 1)) & Blocks::MASK_ROW >> 1;
+                (acc << 6) | u128::from(bits)
+            })
+        }
+
+        let planes = &self.planes;
+        compress(planes[0]) | (compress(planes[1]) << 36) | (compress(planes[2]) << 72)
     }
 
     /// 着手の集合を更新する。
     /// 返される着手の `dst` には必ずブロックがあることが保証される。
+    ///
+    /// 各着手の軌道 (`move_square_direction_iter()` と同じもの) 上で最初にブロックがあるマスを、
+    /// 1 マスずつたどらずにビット演算で求める。探索中に頻繁に呼ばれるので `Ground::trajectory()` は用いない。
     pub fn update_moves(&self, moves: &[Move]) -> Moves {
+        let occupied = self.occupied();
+
         moves
             .iter()
             .copied()
             .filter_map(|mv| {
-                Self::move_square_direction_iter(mv.dst(), mv.direction())
-                    .find_map(|(sq, mv_dir)| self[sq].map(|_| Move::new(mv.src(), sq, mv_dir)))
+                let idx = mv.dst().to_inner();
+                let col = Self::MASK_COL << mv.dst().col().to_inner();
+                // dst から下にあるブロック。
+                let below = occupied & col & !((1 << idx) - 1);
+
+                match mv.direction() {
+                    MoveDirection::Vertical => (below != 0)
+                        .then(|| Self::retarget_move(mv, below.trailing_zeros(), mv.direction())),
+                    MoveDirection::Horizontal => {
+                        // dst から左にあるブロック (dst に最も近いもの)。
+                        let row = Self::MASK_ROW << (8 * mv.dst().row().to_inner());
+                        let left = occupied & row & ((2 << idx) - 1);
+                        if left != 0 {
+                            return Some(Self::retarget_move(
+                                mv,
+                                63 - left.leading_zeros(),
+                                MoveDirection::Horizontal,
+                            ));
+                        }
+
+                        // A 列に達したら下に落ちる。
+                        let idx_a = 8 * u32::from(mv.dst().row().to_inner()) + 1;
+                        let below_a = occupied & (Self::MASK_COL << 1) & !((2 << idx_a) - 1);
+                        (below_a != 0).then(|| {
+                            Self::retarget_move(
+                                mv,
+                                below_a.trailing_zeros(),
+                                MoveDirection::Vertical,
+                            )
+                        })
+                    }
+                }
             })
             .collect()
     }
@@ -427,6 +542,88 @@ impl Blocks {
     /// 着手を行い、(結果、次の保持ブロック、置換前に最後にブロックが通った位置, 同時消し数) を返す。
     /// 着手は合法だと仮定している。
     pub fn do_move(&self, mv: Move, block_holding: Block) -> (Self, Block, BlocksSquare, u8) {
+        let mut blocks_res = self.clone();
+        let (block_holding_nxt, sq_last, erase_count, _) = blocks_res.make_move(mv, block_holding);
+
+        (blocks_res, block_holding_nxt, sq_last, erase_count)
+    }
+
+    /// `do_move()` の参照実装。テストで `make_move()` と比較するためのもの。
+    ///
+    /// 移動経路の走査と落下を 1 マスずつ素朴に行い、`make_move()` とは処理を共有しない。
+    #[cfg(test)]
+    pub(crate) fn do_move_naive(
+        &self,
+        mv: Move,
+        block_holding: Block,
+    ) -> (Self, Block, BlocksSquare, u8) {
+        let square = |col: u8, row: u8| {
+            BlocksSquare::new(
+                BlocksCol::from_inner(col).unwrap(),
+                BlocksRow::from_inner(row).unwrap(),
+            )
+        };
+
+        let block_first = self[mv.dst()].expect("着手先にブロックがない");
+        assert!(block_holding.can_erase(block_first));
+        let block_first = match block_first {
+            Block::Wild => block_holding,
+            _ => block_first,
+        };
+
+        let mut blocks_res = self.clone();
+        let mut block_holding_nxt = block_first;
+        let mut sq_last = mv.dst();
+        let mut erase_count = 0;
+
+        let mut col = mv.dst().col().to_inner();
+        let mut row = mv.dst().row().to_inner();
+        let mut mv_dir = mv.direction();
+        loop {
+            let sq = square(col, row);
+            if let Some(block) = self[sq] {
+                if sq == mv.dst() || block_first.can_erase(block) {
+                    if mv.direction() == MoveDirection::Vertical {
+                        blocks_res.set(sq, None);
+                    } else {
+                        // 上にあるブロックを 1 マスずつ落とす。
+                        for r in (2..=row).rev() {
+                            blocks_res.set(square(col, r), blocks_res[square(col, r - 1)]);
+                        }
+                        blocks_res.set(square(col, 1), None);
+                    }
+                    erase_count += 1;
+                } else {
+                    blocks_res.set(sq, Some(block_first));
+                    block_holding_nxt = block;
+                    break;
+                }
+            }
+            sq_last = sq;
+
+            // 横方向の移動で A 列に達したら下に落ちる。
+            if mv_dir == MoveDirection::Horizontal && col > 1 {
+                col -= 1;
+            } else if row < 6 {
+                mv_dir = MoveDirection::Vertical;
+                row += 1;
+            } else {
+                break;
+            }
+        }
+
+        (blocks_res, block_holding_nxt, sq_last, erase_count)
+    }
+
+    /// 着手をその場で行い、(次の保持ブロック、置換前に最後にブロックが通った位置, 同時消し数, 取り消し情報) を返す。
+    /// 着手は合法だと仮定している。
+    ///
+    /// 取り消し情報を `unmake_move()` に渡すと着手前の盤面に戻る。
+    pub fn make_move(
+        &mut self,
+        mv: Move,
+        block_holding: Block,
+    ) -> (Block, BlocksSquare, u8, BlocksUndo) {
         // mv.dst() には必ずブロックがあるはず。
         unsafe { assert_unchecked!(self[mv.dst()].is_some()) }
         let block_first = unsafe { self[mv.dst()].unwrap_unchecked() };
@@ -442,17 +639,23 @@ impl Blocks {
         };
         unsafe { assert_unchecked!(block_first != Block::Wild) }
 
-        let mut blocks_res = self.clone();
+        // 取り消し情報用に、着手前の盤面と着手により書き換わるマスの集合を記録する。
+        let planes_before = self.planes;
+        let mut mask = 0;
         let mut block_holding_nxt = block_first;
         let mut sq_last = mv.dst();
         let mut erase_count = 0;
 
+        // NOTE: ブロックの移動経路上のマスは、そこに達するまで書き換えられないので、
+        // 盤面をその場で書き換えながら読んでよい。
         macro_rules! erase {
             ($sq:expr) => {{
                 if mv.direction() == MoveDirection::Vertical {
-                    blocks_res[$sq] = None;
+                    mask |= 1 << $sq.to_inner();
+                    self.set($sq, None);
                 } else {
-                    blocks_res.erase_shift($sq);
+                    mask |= Self::MASK_COL << $sq.col().to_inner();
+                    self.erase_shift($sq);
                 }
                 erase_count += 1;
             }};
@@ -462,9 +665,7 @@ impl Blocks {
         erase!(mv.dst());
 
         // その後の移動の処理。
-        let sqs = Self::move_square_direction_iter(mv.dst(), mv.direction())
-            .map(|(sq, _)| sq)
-            .skip(1);
+        let sqs = Self::move_path(mv).skip(1);
         for sq in sqs {
             if let Some(block) = self[sq] {
                 if block_first.can_erase(block) {
@@ -473,7 +674,8 @@ impl Blocks {
                     erase!(sq);
                 } else {
                     // 当たったブロックが消去不能なら置換を行い、そこで止まる。
-                    blocks_res[sq] = Some(block_first);
+                    mask |= 1 << sq.to_inner();
+                    self.set(sq, Some(block_first));
                     block_holding_nxt = block;
                     break;
                 }
@@ -483,14 +685,68 @@ impl Blocks {
             sq_last = sq;
         }
 
-        (blocks_res, block_holding_nxt, sq_last, erase_count)
+        let undo = BlocksUndo {
+            mask,
+            planes: planes_before.map(|plane| plane & mask),
+        };
+
+        (block_holding_nxt, sq_last, erase_count, undo)
+    }
+
+    /// `make_move()` による着手を取り消す。
+    pub fn unmake_move(&mut self, undo: &BlocksUndo) {
+        for (plane, saved) in self.planes.iter_mut().zip(undo.planes) {
+            *plane = (*plane & !undo.mask) | saved;
+        }
+    }
+
+    /// 着手 `mv` により、投げたブロックが盤面内で通りうるマスを順に返す (`mv.dst()` を含む)。
+    ///
+    /// 実際には、ブロックは消去不能なブロックに当たった時点で止まる。
+    pub fn move_path(mv: Move) -> impl std::iter::FusedIterator<Item = BlocksSquare> {
+        Self::move_square_direction_iter(mv.dst(), mv.direction()).map(|(sq, _)| sq)
+    }
+
+    /// 着手 `mv` の `dst` をビット位置 `idx` のマスに、方向を `mv_dir` に置き換える。
+    fn retarget_move(mv: Move, idx: u32, mv_dir: MoveDirection) -> Move {
+        let dst = unsafe { BlocksSquare::from_inner_unchecked(idx as u8) };
+        Move::new(mv.src(), dst, mv_dir)
+    }
+
+    /// ブロックがあるマスのビット集合を返す。
+    fn occupied(&self) -> u64 {
+        self.planes[0] | self.planes[1] | self.planes[2]
+    }
+
+    /// ビットプレーン `planes` のマス `sq` を `block` にする。
+    fn write_planes(planes: &mut [u64; 3], sq: BlocksSquare, block: Option<Block>) {
+        let value = block.map_or(0, Block::to_inner);
+        let bit = 1 << sq.to_inner();
+
+        for (i, plane) in planes.iter_mut().enumerate() {
+            if (value >> i) & 1 != 0 {
+                *plane |= bit;
+            } else {
+                *plane &= !bit;
+            }
+        }
+    }
+
+    /// ビットプレーン `planes` のビット位置 `idx` のマスの値を返す。
+    fn value(planes: &[u64; 3], idx: u32) -> usize {
+        let bit = |plane: u64| ((plane >> idx) & 1) as usize;
+
+        bit(planes[0]) | (bit(planes[1]) << 1) | (bit(planes[2]) << 2)
     }
 
     /// 着手によるブロックの動きをシミュレートする。
+    ///
+    /// ブロック領域には壁/パイプがないので、これは空の地形での `Ground::trajectory()` と一致する
+    /// (テストで確かめている)。`make_move()` から呼ばれ、地形を参照しないで済むようにこちらを用いる。
     fn move_square_direction_iter(
         start: BlocksSquare,
         mv_dir: MoveDirection,
-    ) -> impl Iterator<Item = (BlocksSquare, MoveDirection)> + std::iter::FusedIterator {
+    ) -> impl std::iter::FusedIterator<Item = (BlocksSquare, MoveDirection)> {
         std::iter::successors(Some((start, mv_dir)), |&(sq, mv_dir)| {
             let dir = match mv_dir {
                 MoveDirection::Horizontal => Direction::Left,
@@ -507,12 +763,16 @@ impl Blocks {
         })
     }
 
-    fn erase_shift(&mut self, mut sq: BlocksSquare) {
-        while let Some(sq_nxt) = sq.add_direction(Direction::Up) {
-            self[sq] = self[sq_nxt];
-            sq = sq_nxt;
+    /// マス `sq` のブロックを消し、その上にあるブロックを 1 マスずつ落とす。
+    fn erase_shift(&mut self, sq: BlocksSquare) {
+        let idx = sq.to_inner();
+        let col = Self::MASK_COL << sq.col().to_inner();
+        let above = col & ((1 << idx) - 1);
+        let mask = above | (1 << idx);
+
+        for plane in &mut self.planes {
+            *plane = (*plane & !mask) | ((*plane & above) << 8);
         }
-        self[sq] = None;
     }
 
     fn block_to_char(block: Option<Block>) -> char {
@@ -547,24 +807,22 @@ impl std::ops::Index<(BlocksCol, BlocksRow)> for Blocks {
     }
 }
 
-impl std::ops::IndexMut<(BlocksCol, BlocksRow)> for Blocks {
-    fn index_mut(&mut self, (col, row): (BlocksCol, BlocksRow)) -> &mut Self::Output {
-        self.index_mut(BlocksSquare::new(col, row))
-    }
-}
-
 impl std::ops::Index<BlocksSquare> for Blocks {
     type Output = Option<Block>;
 
     fn index(&self, sq: BlocksSquare) -> &Self::Output {
-        &self.0[sq.to_index()]
+        &Self::BLOCKS_BY_VALUE[Self::value(&self.planes, u32::from(sq.to_inner()))]
     }
 }
 
-impl std::ops::IndexMut<BlocksSquare> for Blocks {
-    fn index_mut(&mut self, sq: BlocksSquare) -> &mut Self::Output {
-        &mut self.0[sq.to_index()]
-    }
+/// `Blocks::make_move()` の取り消し情報。
+///
+/// 着手により書き換わるマス (消去されたマス、および落下が起きた列全体と置換されたマス) の集合と、
+/// それらの着手前の内容を持つ。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BlocksUndo {
+    mask: u64,
+    planes: [u64; 3],
 }
 
 impl std::str::FromStr for Blocks {
@@ -588,7 +846,7 @@ impl std::str::FromStr for Blocks {
 
             for (col, ch) in itertools::zip_eq(BlocksCol::all(), chars) {
                 let block = Self::char_to_block(ch)?;
-                this[(col, row)] = block;
+                this.set(BlocksSquare::new(col, row), block);
             }
         }
 
@@ -612,7 +870,6 @@ impl std::fmt::Display for Blocks {
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum Direction {
-    Up,
     Left,
     Down,
 }
@@ -620,7 +877,6 @@ enum Direction {
 impl Direction {
     fn displacement(self) -> isize {
         match self {
-            Self::Up => -8,
             Self::Left => -1,
             Self::Down => 8,
         }
@@ -680,6 +936,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_blocks_get_set() {
+        let mut blocks = Blocks::new();
+        let mut packeds = std::collections::HashSet::new();
+        packeds.insert(blocks.to_packed());
+
+        let sqs: Vec<_> = itertools::iproduct!(BlocksRow::all(), BlocksCol::all())
+            .map(|(row, col)| BlocksSquare::new(col, row))
+            .collect();
+
+        for (i, &sq) in sqs.iter().enumerate() {
+            let block = Block::from_inner(1 + (i % 5) as u8).unwrap();
+            blocks.set(sq, Some(block));
+            assert_eq!(blocks.get(sq), Some(block));
+            assert_eq!(blocks.block_count(), i as u8 + 1);
+            assert!(packeds.insert(blocks.to_packed()));
+        }
+
+        assert_eq!(blocks.to_packed() >> 108, 0);
+        assert_eq!(blocks.to_array().iter().flatten().count(), 36);
+
+        for &sq in &sqs {
+            blocks.set(sq, None);
+            assert_eq!(blocks.get(sq), None);
+        }
+        assert_eq!(blocks, Blocks::new());
+    }
+
+    #[test]
+    fn test_blocks_to_array() {
+        let mut blocks = parse_blocks(indoc! {"
+            ......
+            ......
+            ......
+            ......
+            ......
+            .1..2.
+        "});
+
+        // set() による書き込みが、直後の読み取りや他の操作に反映される。
+        blocks.set(BlocksSquare::B6, Some(Block::Normal2));
+        blocks.set(BlocksSquare::A6, Some(Block::Normal3));
+        blocks.set(BlocksSquare::E6, None);
+        assert_eq!(blocks[(BLOCKS_COL_B, BLOCKS_ROW_6)], Some(Block::Normal2));
+
+        let array = blocks.to_array();
+        assert_eq!(array.iter().flatten().count(), 2);
+        assert_eq!(
+            array[usize::from(BlocksSquare::A6.to_inner())],
+            Some(Block::Normal3)
+        );
+
+        let (block_holding_nxt, _, erase_count, _) = blocks.make_move(
+            Move::new(GROUND_ROW_6, BlocksSquare::B6, MoveDirection::Horizontal),
+            Block::Normal2,
+        );
+        assert_eq!((block_holding_nxt, erase_count), (Block::Normal3, 1));
+    }
+
+    #[test]
+    fn test_move_square_direction_iter() {
+        // ブロック領域内の動きは、地形上の軌道と一致するはず。
+        let ground = Ground::new();
+        for (row, col) in itertools::iproduct!(BlocksRow::all(), BlocksCol::all()) {
+            let sq = BlocksSquare::new(col, row);
+            for mv_dir in [MoveDirection::Horizontal, MoveDirection::Vertical] {
+                let actual: Vec<_> = Blocks::move_square_direction_iter(sq, mv_dir)
+                    .map(|(sq, mv_dir)| (GroundSquare::from(sq), mv_dir))
+                    .collect();
+                let expect: Vec<_> = ground.trajectory(sq.into(), mv_dir).collect();
+                assert_eq!(actual, expect, "{sq:?} {mv_dir:?}");
+            }
+        }
+    }
+
     #[test]
     fn test_blocks_update_moves() {
         let cases = [(
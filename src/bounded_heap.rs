@@ -34,6 +34,11 @@ impl<T: Ord> BoundedHeap<T> {
         self.heap.peek()
     }
 
+    /// 要素を順不同で列挙する。
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.heap.iter()
+    }
+
     /// `x` を追加する。
     /// 要素数が容量を超える場合、`x` も含めて最大の要素が削除される。
     pub fn insert(&mut self, x: T) {
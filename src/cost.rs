@@ -4,6 +4,7 @@ use crate::block::BlocksSquare;
 use crate::game_mode::GameMode;
 use crate::ground::GroundRow;
 use crate::macros::unreachable_unchecked;
+use crate::move_::Move;
 use crate::position::Position;
 
 /// 所要フレーム数を表す型。
@@ -23,8 +24,85 @@ pub const COST_CLEAR_ERASE_BLOCK: Cost = 11;
 /// NORMAL モードでパーフェクトで面クリアした際の花火演出のコスト。
 pub const COST_CLEAR_FIREWORKS: Cost = 96;
 
+/// 1 回の着手で同時消しされるブロック数の最大値。
+/// (横に投げたブロックが 1 行 6 マスを通過した後、A 列を 5 マス落下するケース)
+pub const ERASE_COUNT_MAX: u8 = 11;
+
 // NOTE: NORMAL モードでの just clear には追加コストがかからない。
 
+/// コストモデル。ソルバー、`Solution::verify()`, `Position::do_move_with()` はこれに従ってコストを計算する。
+///
+/// 既定のモデルは `NesCostModel` で、これと異なるタイミングの仮説やゲームバージョンを試すのに使う。
+/// ソルバーは `throw_cost_min()`, `throw_cost_min_all()` を用いて枝刈りを行うので、
+/// これらは実際のコストを上回ってはならない。
+pub trait CostModel: Send + Sync {
+    /// 自機の移動コストを求める。
+    fn hero_move_cost(&self, from: GroundRow, to: GroundRow) -> Cost;
+
+    /// ブロック投げコストを求める。`src` から `sq_last` に到達しえない場合、`None` を返す。
+    fn try_throw_cost(&self, src: GroundRow, sq_last: BlocksSquare) -> Option<Cost>;
+
+    /// 残りブロック数 `block_count` で面クリアした際の演出コストを求める。
+    fn clear_cost(&self, game_mode: GameMode, block_count: u8, last_stage: bool) -> Cost;
+
+    /// ブロック投げコストを求める。`src` から `sq_last` に到達しうると仮定している。
+    fn throw_cost(&self, src: GroundRow, sq_last: BlocksSquare) -> Cost {
+        self.try_throw_cost(src, sq_last)
+            .expect("到達しえない位置へのブロック投げ")
+    }
+
+    /// 位置 `src` からブロックを投げたときのコストの最小値を求める。
+    /// `src` から到達可能な位置がない場合、`COST_INF` を返す。
+    ///
+    /// 既定の実装は全てのマスを調べるので遅い。探索中に頻繁に呼ばれるので、表引きなどで実装すべき。
+    fn throw_cost_min(&self, src: GroundRow) -> Cost {
+        (BlocksSquare::MIN_VALUE..=BlocksSquare::MAX_VALUE)
+            .filter_map(BlocksSquare::from_inner)
+            .filter_map(|sq| self.try_throw_cost(src, sq))
+            .min()
+            .unwrap_or(COST_INF)
+    }
+
+    /// ブロック投げコストの全体での最小値を求める。
+    fn throw_cost_min_all(&self) -> Cost {
+        GroundRow::all()
+            .into_iter()
+            .map(|src| self.throw_cost_min(src))
+            .min()
+            .unwrap()
+    }
+}
+
+/// NES 版 (v1.0) の所要フレーム数によるコストモデル (既定)。
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct NesCostModel;
+
+impl CostModel for NesCostModel {
+    fn hero_move_cost(&self, from: GroundRow, to: GroundRow) -> Cost {
+        calc_hero_move_cost(from, to)
+    }
+
+    fn try_throw_cost(&self, src: GroundRow, sq_last: BlocksSquare) -> Option<Cost> {
+        try_calc_throw_cost(src, sq_last)
+    }
+
+    fn clear_cost(&self, game_mode: GameMode, block_count: u8, last_stage: bool) -> Cost {
+        calc_clear_cost_by_count(game_mode, block_count, last_stage)
+    }
+
+    fn throw_cost(&self, src: GroundRow, sq_last: BlocksSquare) -> Cost {
+        calc_throw_cost(src, sq_last)
+    }
+
+    fn throw_cost_min(&self, src: GroundRow) -> Cost {
+        calc_throw_cost_min(src)
+    }
+
+    fn throw_cost_min_all(&self) -> Cost {
+        COST_THROW_MIN
+    }
+}
+
 /// 自機の移動コストを求める。
 pub const fn calc_hero_move_cost(from: GroundRow, to: GroundRow) -> Cost {
     let d = from.to_inner().abs_diff(to.to_inner()) as Cost;
@@ -35,6 +113,17 @@ pub const fn calc_hero_move_cost(from: GroundRow, to: GroundRow) -> Cost {
 /// ブロック投げコストを求める。
 /// ブロックを投げた位置を `src`、置換前に最後にブロックが通った位置を `sq_last` とする。
 pub const fn calc_throw_cost(src: GroundRow, sq_last: BlocksSquare) -> Cost {
+    match try_calc_throw_cost(src, sq_last) {
+        Some(cost) => cost,
+        None => unsafe { unreachable_unchecked!() },
+    }
+}
+
+/// ブロック投げコストを求める。`src` から `sq_last` に到達しえない場合、`None` を返す。
+///
+/// NOTE: この表がフレームモデル (`calc_trajectory_throw_costs()`) で再現できることをテストで確かめている。
+/// ただし、フレームモデルはこの表に合わせて定数を定めたものであり、実機での計測との照合ではない。
+pub const fn try_calc_throw_cost(src: GroundRow, sq_last: BlocksSquare) -> Option<Cost> {
     const LEN_SRC: usize = GroundRow::NUM;
     const LEN_SQ: usize = (BlocksSquare::MAX_VALUE + 1) as usize;
 
@@ -321,22 +410,68 @@ pub const fn calc_throw_cost(src: GroundRow, sq_last: BlocksSquare) -> Cost {
         res
     };
 
-    let cost = match TABLE[src.to_index()][sq_last.to_index()] {
-        Some(cost) => cost,
-        None => unsafe { unreachable_unchecked!() },
+    match TABLE[src.to_index()][sq_last.to_index()] {
+        Some(cost) => Some(cost.get()),
+        None => None,
+    }
+}
+
+/// 位置 `src` からブロックを投げたときのコストの最小値を求める。
+/// `src` から到達可能な位置がない場合、`COST_INF` を返す。
+pub const fn calc_throw_cost_min(src: GroundRow) -> Cost {
+    const TABLE: [Cost; GroundRow::NUM] = {
+        let mut res = [COST_INF; GroundRow::NUM];
+
+        let mut inner = BlocksSquare::MIN_VALUE;
+        while inner <= BlocksSquare::MAX_VALUE {
+            if let Some(sq) = BlocksSquare::from_inner(inner) {
+                let mut i = 0;
+                while i < GroundRow::NUM {
+                    if let Some(cost) = try_calc_throw_cost(GroundRow::all()[i], sq) {
+                        if cost < res[i] {
+                            res[i] = cost;
+                        }
+                    }
+                    i += 1;
+                }
+            }
+            inner += 1;
+        }
+
+        res
     };
 
-    cost.get()
+    TABLE[src.to_index()]
 }
 
+/// ブロック投げコストの全体での最小値。
+pub const COST_THROW_MIN: Cost = {
+    let mut res = COST_INF;
+
+    let mut i = 0;
+    while i < GroundRow::NUM {
+        let cost = calc_throw_cost_min(GroundRow::all()[i]);
+        if cost < res {
+            res = cost;
+        }
+        i += 1;
+    }
+
+    res
+};
+
 pub fn calc_clear_cost(game_mode: GameMode, pos: &Position, last_stage: bool) -> Cost {
+    calc_clear_cost_by_count(game_mode, pos.block_count(), last_stage)
+}
+
+fn calc_clear_cost_by_count(game_mode: GameMode, block_count: u8, last_stage: bool) -> Cost {
     const BLOCK_COUNT_PERFECT: u8 = 3;
 
     if last_stage {
         0
     } else {
-        let cost_erase = COST_CLEAR_ERASE_BLOCK * Cost::from(pos.block_count());
-        let cost_fireworks = if game_mode.is_normal() && pos.block_count() <= BLOCK_COUNT_PERFECT {
+        let cost_erase = COST_CLEAR_ERASE_BLOCK * Cost::from(block_count);
+        let cost_fireworks = if game_mode.is_normal() && block_count <= BLOCK_COUNT_PERFECT {
             COST_CLEAR_FIREWORKS
         } else {
             0
@@ -344,3 +479,62 @@ pub fn calc_clear_cost(game_mode: GameMode, pos: &Position, last_stage: bool) ->
         cost_erase + cost_fireworks
     }
 }
+
+/// 合法手がある局面から面クリアまでにかかる残りコストの下界を求める (コストはモデル `cost_model` に従う)。
+/// `moves_legal` は `pos` における合法手の集合 (空でない) とする。
+///
+/// 残りコストを過大評価することはない (許容的である)。
+/// 残り手数内に面クリアできないことが明らかな場合 (残り手数が 0 の場合を含む)、`None` を返す。
+pub fn calc_remaining_cost_lower_bound<M: CostModel + ?Sized>(
+    cost_model: &M,
+    game_mode: GameMode,
+    pos: &Position,
+    moves_legal: &[Move],
+    last_stage: bool,
+) -> Option<Cost> {
+    // 残り着手数の下界。
+    // 合法手がある以上少なくとも 1 手は必要で、また 1 手で消せるブロック数には上限がある。
+    let block_count_excess = pos
+        .block_count()
+        .saturating_sub(game_mode.block_count_target());
+    let move_count_min = block_count_excess.div_ceil(ERASE_COUNT_MAX).max(1);
+    if move_count_min > pos.move_count_remain() {
+        return None;
+    }
+    let move_count_min = Cost::from(move_count_min);
+
+    // 次の着手のコストの下界。
+    // 最終面で、かつ次の着手が最終手となりうるなら、ブロック投げコストは 1 とみなす。
+    let cost_first = moves_legal
+        .iter()
+        .map(|mv| {
+            let cost_throw = if last_stage && move_count_min == 1 {
+                1
+            } else {
+                cost_model.throw_cost_min(mv.src())
+            };
+            cost_model.hero_move_cost(pos.hero_row(), mv.src()) + cost_throw
+        })
+        .min()
+        .expect("合法手の集合が空であってはならない");
+
+    // 2 手目以降のコストの下界 (自機の移動コストは 0 とみなす)。
+    let cost_throw_min = cost_model.throw_cost_min_all();
+    let cost_rest = match (move_count_min, last_stage) {
+        (1, _) => 0,
+        (_, false) => cost_throw_min * (move_count_min - 1),
+        (_, true) => cost_throw_min * (move_count_min - 2) + 1,
+    };
+
+    // 面クリア時の演出コストの下界。
+    // 最終的なブロック数は (現在のブロック数 - 1) 以下かつ目標ブロック数以下のどれかになる。
+    let block_count_final_max = game_mode
+        .block_count_target()
+        .min(pos.block_count().saturating_sub(1));
+    let cost_clear = (0..=block_count_final_max)
+        .map(|block_count| cost_model.clear_cost(game_mode, block_count, last_stage))
+        .min()
+        .unwrap();
+
+    Some(cost_first + cost_rest + cost_clear)
+}
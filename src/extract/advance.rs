@@ -1,6 +1,6 @@
 use byteorder::{ByteOrder as _, LE};
 
-use crate::block::{Block, Blocks, BlocksCol, BlocksRow};
+use crate::block::{Block, Blocks, BlocksCol, BlocksRow, BlocksSquare};
 use crate::ground::{Ground, GroundCol, GroundElement, GroundRow};
 use crate::problem::Problem;
 use crate::rom::Rom;
@@ -34,7 +34,7 @@ pub fn extract_advance_problem(rom: &Rom, stage: u8) -> Problem {
                     // ADVANCE モードでは盤面にワイルドカードが現れることはない。
                     _ => panic!("無効な盤面ブロック値: {block}"),
                 };
-                blocks[(col, row)] = block;
+                blocks.set(BlocksSquare::new(col, row), block);
             }
         }
 
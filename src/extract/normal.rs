@@ -46,13 +46,13 @@ pub fn extract_normal_blocks(rom: &Rom, stage: u8, rng_state: [u8; 2], wild: boo
                 // ここまでで生成されるブロックはワイルドカードではない。
                 _ => panic!("無効な盤面ブロック値: {block}"),
             };
-            blocks[(col, row)] = block;
+            blocks.set(BlocksSquare::new(col, row), block);
         }
     }
 
     // ワイルドカードは決まった位置にしか配置されない。
     if wild {
-        blocks[BlocksSquare::B5] = Some(Block::Wild);
+        blocks.set(BlocksSquare::B5, Some(Block::Wild));
     }
 
     blocks
@@ -75,6 +75,45 @@ fn extract_blocks_part(rom: &Rom, params: &BlocksParams, rng: &mut BlocksRng, bu
     }
 }
 
+/// NORMAL モードの盤面の各色のブロック数。
+///
+/// インデックスは (配置領域の種類 << 2 | 乱数由来の 2bit, 前半/後半)。
+/// 要素は色 1..=4 のブロック数。
+pub(crate) const NORMAL_BLOCK_COUNTS_TABLE: [[[u8; 4]; 2]; 12] = [
+    [[3, 2, 2, 2], [4, 4, 4, 4]],
+    [[2, 3, 2, 2], [4, 4, 4, 4]],
+    [[2, 2, 3, 2], [4, 4, 4, 4]],
+    [[2, 2, 2, 3], [4, 4, 4, 4]],
+    [[2, 2, 3, 3], [5, 5, 5, 5]],
+    [[3, 3, 2, 2], [5, 5, 5, 5]],
+    [[2, 3, 2, 3], [5, 5, 5, 5]],
+    [[3, 2, 3, 2], [5, 5, 5, 5]],
+    [[2, 3, 3, 3], [7, 6, 6, 6]],
+    [[3, 2, 3, 3], [6, 7, 6, 6]],
+    [[3, 3, 2, 3], [6, 6, 7, 6]],
+    [[3, 3, 3, 2], [6, 6, 6, 7]],
+];
+
+/// NORMAL モードの盤面のブロック配置領域。
+///
+/// インデックスは (配置領域の種類, 前半/後半)。
+/// 要素は `行 * 8 + 列` (いずれも 0-based) の形のインデックスで、この順にブロックが配置される。
+#[rustfmt::skip]
+pub(crate) const NORMAL_IDXS_TABLE: [[&[usize]; 2]; 3] = [
+    [
+        &[8, 9, 10, 11, 12, 20, 28, 36, 44],
+        &[16, 17, 18, 19, 24, 25, 26, 27, 32, 33, 34, 35, 40, 41, 42, 43],
+    ],
+    [
+        &[0, 1, 2, 3, 4, 12, 20, 28, 36, 44],
+        &[8, 9, 10, 11, 16, 17, 18, 19, 24, 25, 26, 27, 32, 33, 34, 35, 40, 41, 42, 43],
+    ],
+    [
+        &[0, 1, 2, 3, 4, 5, 13, 21, 29, 37, 45],
+        &[8, 9, 10, 11, 12, 16, 17, 18, 19, 20, 24, 25, 26, 27, 28, 32, 33, 34, 35, 36, 40, 41, 42, 43, 44],
+    ],
+];
+
 #[derive(Debug)]
 struct BlocksParams {
     block_counts: [u8; 4],
@@ -91,45 +130,14 @@ impl BlocksParams {
             2, 2, 2, 2, 2, 0, 1, 1,
         ];
 
-        const BLOCK_COUNTS_TABLE: [[[u8; 4]; 2]; 12] = [
-            [[3, 2, 2, 2], [4, 4, 4, 4]],
-            [[2, 3, 2, 2], [4, 4, 4, 4]],
-            [[2, 2, 3, 2], [4, 4, 4, 4]],
-            [[2, 2, 2, 3], [4, 4, 4, 4]],
-            [[2, 2, 3, 3], [5, 5, 5, 5]],
-            [[3, 3, 2, 2], [5, 5, 5, 5]],
-            [[2, 3, 2, 3], [5, 5, 5, 5]],
-            [[3, 2, 3, 2], [5, 5, 5, 5]],
-            [[2, 3, 3, 3], [7, 6, 6, 6]],
-            [[3, 2, 3, 3], [6, 7, 6, 6]],
-            [[3, 3, 2, 3], [6, 6, 7, 6]],
-            [[3, 3, 3, 2], [6, 6, 6, 7]],
-        ];
-
-        #[rustfmt::skip]
-        const IDXS_TABLE: [[&[usize]; 2]; 3] = [
-            [
-                &[8, 9, 10, 11, 12, 20, 28, 36, 44],
-                &[16, 17, 18, 19, 24, 25, 26, 27, 32, 33, 34, 35, 40, 41, 42, 43],
-            ],
-            [
-                &[0, 1, 2, 3, 4, 12, 20, 28, 36, 44],
-                &[8, 9, 10, 11, 16, 17, 18, 19, 24, 25, 26, 27, 32, 33, 34, 35, 40, 41, 42, 43],
-            ],
-            [
-                &[0, 1, 2, 3, 4, 5, 13, 21, 29, 37, 45],
-                &[8, 9, 10, 11, 12, 16, 17, 18, 19, 20, 24, 25, 26, 27, 28, 32, 33, 34, 35, 36, 40, 41, 42, 43, 44],
-            ],
-        ];
-
         let stage_kind = STAGE_KIND_TABLE[usize::from(stage % 32)];
 
         let block_counts = {
             let idx = (stage_kind << 2) | usize::from((rng_state[0] >> 2) & 3);
-            BLOCK_COUNTS_TABLE[idx][usize::from(second)]
+            NORMAL_BLOCK_COUNTS_TABLE[idx][usize::from(second)]
         };
 
-        let idxs = IDXS_TABLE[stage_kind][usize::from(second)];
+        let idxs = NORMAL_IDXS_TABLE[stage_kind][usize::from(second)];
 
         Self { block_counts, idxs }
     }
@@ -0,0 +1,372 @@
+use crate::block::{Block, Blocks, BlocksCol, BlocksRow, BlocksSquare};
+use crate::cost::Cost;
+use crate::extract::{NORMAL_BLOCK_COUNTS_TABLE, NORMAL_IDXS_TABLE};
+use crate::ground::{Ground, GroundSquare};
+use crate::problem::Problem;
+use crate::solver::{solve_problem, Solution, SolverArgument, SolverConfig};
+
+/// 問題生成用の疑似乱数生成器 (SplitMix64)。
+///
+/// 同じシードからは、環境によらず常に同じ系列を生成する。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GeneratorRng {
+    state: u64,
+}
+
+impl GeneratorRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// `0..n` の範囲の乱数を返す。`n` は正でなければならない。
+    pub fn gen_below(&mut self, n: usize) -> usize {
+        assert!(n > 0);
+
+        ((u128::from(self.next_u64()) * n as u128) >> 64) as usize
+    }
+
+    /// `xs` をランダムに並べ替える。
+    pub fn shuffle<T>(&mut self, xs: &mut [T]) {
+        for i in (1..xs.len()).rev() {
+            xs.swap(i, self.gen_below(i + 1));
+        }
+    }
+}
+
+/// 生成する問題のモード。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GeneratorMode {
+    Normal {
+        /// ブロック規定数。
+        block_count_target: u8,
+        /// 盤面にワイルドカードを配置するかどうか。
+        wild: bool,
+    },
+    Advance {
+        /// 手数制限 (1 以上)。保持ブロックはワイルドカード以外からランダムに選ばれる。
+        move_count_remain: u8,
+    },
+}
+
+/// シードから決定的にランダムな問題を生成する。
+///
+/// ブロック配置は、ADVANCE モードでも NORMAL モードの配置規則 (配置領域と各色のブロック数) に従う。
+#[derive(Clone, Debug)]
+pub struct ProblemGenerator {
+    rng: GeneratorRng,
+    mode: GeneratorMode,
+    ground: Ground,
+}
+
+impl ProblemGenerator {
+    /// 地形は壁/パイプのないものとなる。
+    pub fn new(seed: u64, mode: GeneratorMode) -> Self {
+        if let GeneratorMode::Advance { move_count_remain } = mode {
+            assert!(move_count_remain > 0, "手数制限は 1 以上でなければならない");
+        }
+
+        Self {
+            rng: GeneratorRng::new(seed),
+            mode,
+            ground: Ground::new(),
+        }
+    }
+
+    /// 地形を設定する。
+    ///
+    /// `ground` は `Problem` と同じ制約を満たし、かついずれかの配置領域と重なってはならない。
+    /// そうでない場合、`generate()` が panic する。
+    pub fn with_ground(mut self, ground: Ground) -> Self {
+        self.ground = ground;
+        self
+    }
+
+    pub fn mode(&self) -> GeneratorMode {
+        self.mode
+    }
+
+    pub fn ground(&self) -> &Ground {
+        &self.ground
+    }
+
+    /// 問題を 1 つ生成する。
+    pub fn generate(&mut self) -> Problem {
+        match self.mode {
+            GeneratorMode::Normal {
+                block_count_target,
+                wild,
+            } => {
+                let blocks = generate_normal_blocks(&mut self.rng, &self.ground, wild);
+                Problem::new_normal(self.ground.clone(), blocks, block_count_target)
+                    .expect("問題が NORMAL モードの制約を満たしていない")
+            }
+            GeneratorMode::Advance { move_count_remain } => {
+                let blocks = generate_normal_blocks(&mut self.rng, &self.ground, false);
+                let block_holding = Block::from_inner(1 + self.rng.gen_below(4) as u8).unwrap();
+                Problem::new_advance(
+                    self.ground.clone(),
+                    blocks,
+                    block_holding,
+                    move_count_remain,
+                )
+                .expect("問題が ADVANCE モードの制約を満たしていない")
+            }
+        }
+    }
+
+    /// `solve_problem()` で解ける問題が得られるまで、最大 `attempt_max` 回問題を生成する。
+    /// 得られた問題とその最適解、コストを返す。
+    ///
+    /// 探索が打ち切られる設定の場合、それまでに解が見つかった問題も解けるものとみなす。
+    pub fn generate_solvable(
+        &mut self,
+        config: &SolverConfig,
+        attempt_max: usize,
+    ) -> Option<(Problem, Solution, Cost)> {
+        for _ in 0..attempt_max {
+            let problem = self.generate();
+            let solver_arg = SolverArgument::new(&problem, config);
+            if let Some((solution, cost)) = solve_problem(&solver_arg) {
+                return Some((problem, solution, cost));
+            }
+        }
+
+        None
+    }
+}
+
+impl Iterator for ProblemGenerator {
+    type Item = Problem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.generate())
+    }
+}
+
+/// NORMAL モードの配置規則に従い、ランダムなブロック配置を生成する。
+///
+/// 配置領域は地形 `ground` の壁/パイプと重ならないものからランダムに選ぶ。
+/// そのような配置領域がなければ panic する。
+/// `wild` が真ならば、ROM と同様に B5 をワイルドカードで置き換える。
+pub fn generate_normal_blocks(rng: &mut GeneratorRng, ground: &Ground, wild: bool) -> Blocks {
+    let stage_kinds: Vec<_> = (0..NORMAL_IDXS_TABLE.len())
+        .filter(|&stage_kind| {
+            NORMAL_IDXS_TABLE[stage_kind]
+                .iter()
+                .flat_map(|idxs| idxs.iter())
+                .all(|&idx| ground[GroundSquare::from(idx_to_square(idx))].is_none())
+        })
+        .collect();
+    assert!(
+        !stage_kinds.is_empty(),
+        "地形と重ならないブロック配置領域がない"
+    );
+
+    let stage_kind = stage_kinds[rng.gen_below(stage_kinds.len())];
+    let block_counts_part = NORMAL_BLOCK_COUNTS_TABLE[(stage_kind << 2) | rng.gen_below(4)];
+
+    let mut blocks = Blocks::new();
+
+    for (block_counts, idxs) in itertools::zip_eq(block_counts_part, NORMAL_IDXS_TABLE[stage_kind])
+    {
+        let mut bs: Vec<_> = (1..=4)
+            .zip(block_counts)
+            .flat_map(|(b, count)| std::iter::repeat_n(b, usize::from(count)))
+            .collect();
+        assert_eq!(bs.len(), idxs.len());
+        rng.shuffle(&mut bs);
+
+        for (&idx, b) in itertools::zip_eq(idxs, bs) {
+            blocks.set(idx_to_square(idx), Block::from_inner(b));
+        }
+    }
+
+    if wild {
+        blocks.set(BlocksSquare::B5, Some(Block::Wild));
+    }
+
+    blocks
+}
+
+/// 配置領域のインデックス (`行 * 8 + 列`) をマスに変換する。
+fn idx_to_square(idx: usize) -> BlocksSquare {
+    let col = BlocksCol::all()[idx % 8];
+    let row = BlocksRow::all()[idx / 8];
+
+    BlocksSquare::new(col, row)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_mode::GameMode;
+    use crate::test_util::advance_problem;
+
+    use super::*;
+
+    fn all_squares() -> impl Iterator<Item = BlocksSquare> {
+        (BlocksSquare::MIN_VALUE..=BlocksSquare::MAX_VALUE).filter_map(BlocksSquare::from_inner)
+    }
+
+    #[test]
+    fn test_generator_rng() {
+        let mut rng = GeneratorRng::new(0);
+        let xs: Vec<_> = (0..3).map(|_| rng.next_u64()).collect();
+        // SplitMix64 の既知の出力。
+        assert_eq!(
+            xs,
+            [
+                0xE220_A839_7B1D_CDAF,
+                0x6E78_9E6A_A1B9_65F4,
+                0x06C4_5D18_8009_454F
+            ]
+        );
+
+        let mut rng = GeneratorRng::new(1);
+        for n in 1..20 {
+            assert!(rng.gen_below(n) < n);
+        }
+    }
+
+    #[test]
+    fn test_generate_deterministic() {
+        let mode = GeneratorMode::Normal {
+            block_count_target: 5,
+            wild: true,
+        };
+
+        let gen = |seed| -> Vec<_> {
+            ProblemGenerator::new(seed, mode)
+                .take(10)
+                .map(|problem| problem.to_string())
+                .collect()
+        };
+
+        assert_eq!(gen(42), gen(42));
+        assert_ne!(gen(42), gen(43));
+    }
+
+    #[test]
+    fn test_generate_normal() {
+        for seed in 0..100 {
+            let wild = seed % 2 == 0;
+            let problem = ProblemGenerator::new(
+                seed,
+                GeneratorMode::Normal {
+                    block_count_target: 5,
+                    wild,
+                },
+            )
+            .generate();
+            assert_eq!(
+                problem.game_mode(),
+                GameMode::Normal {
+                    block_count_target: 5
+                }
+            );
+
+            let blocks = problem.blocks();
+            assert_eq!(blocks.get(BlocksSquare::B5) == Some(Block::Wild), wild);
+
+            // いずれかの (配置領域, 各色のブロック数) の組に一致するはず。
+            let matched = (0..NORMAL_BLOCK_COUNTS_TABLE.len()).any(|i| {
+                let idxs = NORMAL_IDXS_TABLE[i >> 2];
+                let [counts_first, counts_second] = NORMAL_BLOCK_COUNTS_TABLE[i];
+
+                let region_ok = all_squares().all(|sq| {
+                    let in_region = idxs
+                        .iter()
+                        .flat_map(|idxs| idxs.iter())
+                        .any(|&idx| idx_to_square(idx) == sq);
+                    blocks.get(sq).is_some() == in_region
+                });
+
+                let counts_ok = (1..=4).all(|b| {
+                    let block = Block::from_inner(b).unwrap();
+                    let count = all_squares()
+                        .filter(|&sq| blocks.get(sq) == Some(block))
+                        .count();
+                    let expect = usize::from(
+                        counts_first[usize::from(b - 1)] + counts_second[usize::from(b - 1)],
+                    );
+                    // ワイルドカードは 1 個のブロックを置き換える。
+                    count == expect || (wild && count + 1 == expect)
+                });
+
+                region_ok && counts_ok
+            });
+            assert!(matched, "seed={seed}:\n{problem}");
+
+            // 文字列表現を経由しても同じ問題になる。
+            let reparsed: Problem = problem.to_string().parse().unwrap();
+            assert_eq!(reparsed.to_string(), problem.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generate_advance() {
+        let problem_ground = advance_problem(3);
+
+        let mut generator = ProblemGenerator::new(
+            7,
+            GeneratorMode::Advance {
+                move_count_remain: 13,
+            },
+        )
+        .with_ground(problem_ground.ground().clone());
+
+        for problem in generator.by_ref().take(20) {
+            let GameMode::Advance {
+                block_holding,
+                move_count_remain,
+            } = problem.game_mode()
+            else {
+                panic!("ADVANCE モードの問題が生成されていない");
+            };
+            assert!(block_holding.is_normal());
+            assert_eq!(move_count_remain, 13);
+            assert_eq!(
+                problem.ground().to_string(),
+                problem_ground.ground().to_string()
+            );
+            assert!(!problem.blocks().to_array().contains(&Some(Block::Wild)));
+        }
+
+        let config = SolverConfig::default();
+
+        // 1 手で 3 個以下まで減らすことはできない。
+        let mut generator = ProblemGenerator::new(
+            7,
+            GeneratorMode::Advance {
+                move_count_remain: 1,
+            },
+        );
+        assert!(generator.generate_solvable(&config, 3).is_none());
+    }
+
+    #[test]
+    fn test_generate_solvable() {
+        let config = SolverConfig::default();
+
+        let mut generator = ProblemGenerator::new(
+            3,
+            GeneratorMode::Normal {
+                block_count_target: 5,
+                wild: false,
+            },
+        );
+        for _ in 0..3 {
+            let (problem, solution, cost) = generator.generate_solvable(&config, 100).unwrap();
+            let solver_arg = SolverArgument::new(&problem, &config);
+            assert_eq!(solution.verify(&solver_arg).unwrap(), cost);
+        }
+    }
+}
@@ -2,8 +2,9 @@ use std::fmt::Write as _;
 
 use anyhow::{anyhow, ensure};
 
-use crate::block::{BlocksCol, BlocksRow};
+use crate::block::{BlocksCol, BlocksRow, BlocksSquare};
 use crate::macros::assert_unchecked;
+use crate::move_::MoveDirection;
 
 /// `Ground` の列。
 #[repr(u8)]
@@ -59,6 +60,11 @@ impl GroundCol {
         (self.to_inner() - 1) as usize
     }
 
+    /// 1 つ左の列を返す。
+    pub const fn prev(self) -> Option<Self> {
+        Self::from_inner(self.to_inner() - 1)
+    }
+
     /// 1 つ右の列を返す。
     pub const fn next(self) -> Option<Self> {
         Self::from_inner(self.to_inner() + 1)
@@ -199,6 +205,11 @@ impl GroundRow {
         Self::from_inner(self.to_inner() - 1)
     }
 
+    /// 1 つ下の行を返す。
+    pub const fn next(self) -> Option<Self> {
+        Self::from_inner(self.to_inner() + 1)
+    }
+
     /// ブロック領域の行かどうかを返す。
     pub const fn is_blocks_area(self) -> bool {
         self.to_inner() >= GROUND_ROW_7.to_inner()
@@ -291,12 +302,37 @@ impl GroundSquare {
         8 * self.row.to_index() + self.col.to_index()
     }
 
+    pub const fn col(self) -> GroundCol {
+        self.col
+    }
+
+    pub const fn row(self) -> GroundRow {
+        self.row
+    }
+
     /// 左下 6x6 のブロック領域かどうかを返す。
     pub const fn is_blocks_area(self) -> bool {
         self.col.is_blocks_area() && self.row.is_blocks_area()
     }
 }
 
+impl From<BlocksSquare> for GroundSquare {
+    fn from(bsq: BlocksSquare) -> Self {
+        Self::new(bsq.col().into(), bsq.row().into())
+    }
+}
+
+impl TryFrom<GroundSquare> for BlocksSquare {
+    type Error = anyhow::Error;
+
+    fn try_from(gsq: GroundSquare) -> Result<Self, Self::Error> {
+        let col = BlocksCol::try_from(gsq.col)?;
+        let row = BlocksRow::try_from(gsq.row)?;
+
+        Ok(Self::new(col, row))
+    }
+}
+
 /// `Ground` の要素。
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -351,6 +387,34 @@ impl Ground {
         Self([None; 8 * 12])
     }
 
+    /// 行 `row` から投げたブロックの、地形のみを考慮した軌道を返す。
+    ///
+    /// 自機は H 列の右にいて、ブロックは H 列から左に移動する。
+    /// 壁/パイプに当たるか A 列に達したら下に落ち、最下段に達するか壁/パイプに当たったら止まる。
+    /// H 列の壁/パイプに当たった場合、ブロックは地形内に入らないので空の軌道を返す。
+    ///
+    /// 壁とパイプは区別しない (どちらもブロックを止める)。
+    /// 既存の問題では落下する列に壁/パイプがないので、落下中に止まる場合の動きは実機と照合できていない。
+    pub fn throw_trajectory(&self, row: GroundRow) -> Trajectory<'_> {
+        let start = GroundSquare::new(GROUND_COL_H, row);
+        if self[start].is_some() {
+            return Trajectory {
+                ground: self,
+                cur: None,
+            };
+        }
+
+        self.trajectory(start, MoveDirection::Horizontal)
+    }
+
+    /// マス `start` を方向 `mv_dir` に移動するブロックの、地形のみを考慮した軌道を返す (`start` 自身を含む)。
+    pub fn trajectory(&self, start: GroundSquare, mv_dir: MoveDirection) -> Trajectory<'_> {
+        Trajectory {
+            ground: self,
+            cur: Some((start, mv_dir)),
+        }
+    }
+
     fn elem_to_char(elem: Option<GroundElement>) -> char {
         match elem {
             None => Self::CHAR_NONE,
@@ -440,6 +504,54 @@ impl std::fmt::Display for Ground {
     }
 }
 
+/// 投げたブロックの地形上の軌道。ブロックが通るマスとそのマスに入ったときの移動方向を順に返す。
+///
+/// 盤面のブロックは考慮しない (ブロックに当たった後の動きは `Blocks` が扱う)。
+#[derive(Clone, Debug)]
+pub struct Trajectory<'a> {
+    ground: &'a Ground,
+    cur: Option<(GroundSquare, MoveDirection)>,
+}
+
+impl Trajectory<'_> {
+    /// マス `sq` にいるブロックが落下できる場合、1 つ下のマスを返す。
+    fn fall(&self, sq: GroundSquare) -> Option<GroundSquare> {
+        let sq_nxt = GroundSquare::new(sq.col, sq.row.next()?);
+
+        self.ground[sq_nxt].is_none().then_some(sq_nxt)
+    }
+}
+
+impl Iterator for Trajectory<'_> {
+    type Item = (GroundSquare, MoveDirection);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (sq, mv_dir) = self.cur?;
+
+        self.cur = match mv_dir {
+            MoveDirection::Horizontal => {
+                let sq_left = sq.col.prev().map(|col| GroundSquare::new(col, sq.row));
+                match sq_left {
+                    Some(sq_left) if self.ground[sq_left].is_none() => {
+                        Some((sq_left, MoveDirection::Horizontal))
+                    }
+                    // 壁/パイプに当たるか A 列に達したら下に落ちる。
+                    _ => self
+                        .fall(sq)
+                        .map(|sq_nxt| (sq_nxt, MoveDirection::Vertical)),
+                }
+            }
+            MoveDirection::Vertical => self
+                .fall(sq)
+                .map(|sq_nxt| (sq_nxt, MoveDirection::Vertical)),
+        };
+
+        Some((sq, mv_dir))
+    }
+}
+
+impl std::iter::FusedIterator for Trajectory<'_> {}
+
 #[cfg(test)]
 mod tests {
     use indoc::indoc;
@@ -489,4 +601,102 @@ mod tests {
             assert_eq!(ground.to_string(), case);
         }
     }
+
+    #[test]
+    fn test_throw_trajectory() {
+        /// "H3 G3 / G4 G5" のような文字列を軌道に変換する ('/' 以降は落下)。
+        fn parse_trajectory(s: &str) -> Vec<(GroundSquare, MoveDirection)> {
+            let mut mv_dir = MoveDirection::Horizontal;
+            let mut res = vec![];
+            for token in s.split_whitespace() {
+                if token == "/" {
+                    mv_dir = MoveDirection::Vertical;
+                    continue;
+                }
+                let col = GroundCol::from_inner(token.as_bytes()[0] - b'A' + 1).unwrap();
+                let row = GroundRow::from_inner(token[1..].parse().unwrap()).unwrap();
+                res.push((GroundSquare::new(col, row), mv_dir));
+            }
+            res
+        }
+
+        let ground = parse_ground(indoc! {"
+            #####...
+            ###.....
+            ###.....
+            #.||....
+            #.......
+            |.......
+            ........
+            ......|.
+            .......|
+            ........
+            ........
+            ........
+        "});
+
+        let cases = [
+            // 壁に当たって落ちる。
+            (
+                GROUND_ROW_1,
+                "H1 G1 F1 / F2 F3 F4 F5 F6 F7 F8 F9 F10 F11 F12",
+            ),
+            // パイプに当たって落ちる。
+            (GROUND_ROW_4, "H4 G4 F4 E4 / E5 E6 E7 E8 E9 E10 E11 E12"),
+            // 落下中に壁/パイプに当たったら止まる。
+            (GROUND_ROW_3, "H3 G3 F3 E3 D3"),
+            (GROUND_ROW_6, "H6 G6 F6 E6 D6 C6 B6 / B7 B8 B9 B10 B11 B12"),
+            (GROUND_ROW_8, "H8"),
+            // H 列に当たるなら地形内に入らない。
+            (GROUND_ROW_9, ""),
+            // A 列に達したら落ちる。
+            (GROUND_ROW_10, "H10 G10 F10 E10 D10 C10 B10 A10 / A11 A12"),
+        ];
+
+        for (row, expect) in cases {
+            let trajectory: Vec<_> = ground.throw_trajectory(row).collect();
+            assert_eq!(trajectory, parse_trajectory(expect), "{row:?}");
+        }
+    }
+    /// 既存の問題について、投げたブロックが最初にブロック領域に入るマスが、
+    /// 「下 6 行は壁/パイプがなければ F 列に入る。上 6 行は最初に当たった壁/パイプの 1 つ右の列を落下する」
+    /// という規則と一致することを確かめる。
+    #[test]
+    fn test_throw_trajectory_advance() {
+        for (name, problem) in crate::test_util::advance_problems() {
+            let ground = problem.ground();
+            for row in GroundRow::all() {
+                let actual = ground
+                    .throw_trajectory(row)
+                    .find(|(sq, _)| sq.is_blocks_area());
+
+                let expect = if row.is_blocks_area() {
+                    GroundCol::all()
+                        .into_iter()
+                        .all(|col| ground[(col, row)].is_none())
+                        .then(|| {
+                            (
+                                GroundSquare::new(GROUND_COL_F, row),
+                                MoveDirection::Horizontal,
+                            )
+                        })
+                } else {
+                    let col = GroundCol::all()
+                        .into_iter()
+                        .rev()
+                        .find(|&col| ground[(col, row)].is_some())
+                        .map_or(Some(GROUND_COL_A), GroundCol::next);
+                    col.map(|col| {
+                        (
+                            GroundSquare::new(col, GROUND_ROW_7),
+                            MoveDirection::Vertical,
+                        )
+                    })
+                    .filter(|(sq, _)| sq.is_blocks_area())
+                };
+
+                assert_eq!(actual, expect, "{name} {row:?}");
+            }
+        }
+    }
 }
@@ -2,24 +2,37 @@ mod block;
 mod bounded_heap;
 mod cost;
 mod extract;
+mod frame_model;
 mod game_mode;
+mod generator;
 mod ground;
 mod macros;
 mod move_;
+mod move_filter;
+mod observer;
 mod position;
 mod problem;
 mod rom;
+mod score;
 mod solver;
+#[cfg(test)]
+mod test_util;
+mod transposition;
 mod util;
 
 pub use self::block::*;
 pub use self::bounded_heap::*;
 pub use self::cost::*;
 pub use self::extract::*;
+pub use self::frame_model::*;
 pub use self::game_mode::*;
+pub use self::generator::*;
 pub use self::ground::*;
 pub use self::move_::*;
+pub use self::move_filter::*;
+pub use self::observer::*;
 pub use self::position::*;
 pub use self::problem::*;
 pub use self::rom::*;
+pub use self::score::*;
 pub use self::solver::*;
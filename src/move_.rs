@@ -1,3 +1,4 @@
+use anyhow::{anyhow, ensure, Context as _};
 use arrayvec::ArrayVec;
 
 use crate::block::BlocksSquare;
@@ -10,6 +11,26 @@ pub enum MoveDirection {
     Vertical,
 }
 
+impl MoveDirection {
+    const CHAR_HORIZONTAL: char = '>';
+    const CHAR_VERTICAL: char = 'v';
+
+    fn to_char(self) -> char {
+        match self {
+            Self::Horizontal => Self::CHAR_HORIZONTAL,
+            Self::Vertical => Self::CHAR_VERTICAL,
+        }
+    }
+
+    fn from_char(ch: char) -> Option<Self> {
+        match ch {
+            Self::CHAR_HORIZONTAL => Some(Self::Horizontal),
+            Self::CHAR_VERTICAL => Some(Self::Vertical),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Move {
     src: GroundRow,
@@ -36,4 +57,151 @@ impl Move {
     }
 }
 
+/// 着手記法で表す: "r9>F3" (9 行目から横に投げ、最初に F3 に当たる), "r2vA1" (2 行目から投げ、落下して最初に A1 に当たる)。
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "r{}{}{}",
+            self.src.to_inner(),
+            self.dir.to_char(),
+            self.dst
+        )
+    }
+}
+
 pub type Moves = ArrayVec<Move, 12>;
+
+/// 着手記法の 1 トークン。
+///
+/// 完全な形は `Move` の表示と同じ "r9>F3" だが、投げる行 ("r9" または "9") と
+/// 移動方向および最初に当たるマス (">F3") の一方を省略してもよい。
+/// 省略した場合、局面によっては複数の着手に該当しうる。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MoveNotation {
+    pub src: Option<GroundRow>,
+    pub target: Option<(MoveDirection, BlocksSquare)>,
+}
+
+impl MoveNotation {
+    /// 着手 `mv` がこの記法に該当するかどうかを返す。
+    pub fn matches(&self, mv: Move) -> bool {
+        self.src.is_none_or(|src| src == mv.src())
+            && self
+                .target
+                .is_none_or(|(dir, dst)| dir == mv.direction() && dst == mv.dst())
+    }
+}
+
+impl From<Move> for MoveNotation {
+    fn from(mv: Move) -> Self {
+        Self {
+            src: Some(mv.src()),
+            target: Some((mv.direction(), mv.dst())),
+        }
+    }
+}
+
+impl std::str::FromStr for MoveNotation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let body = s.strip_prefix('r').unwrap_or(s);
+        let idx = body
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(body.len());
+        let (src, target) = body.split_at(idx);
+
+        let src = if src.is_empty() {
+            ensure!(body.len() == s.len(), "'r' の後に行がない: '{s}'");
+            None
+        } else {
+            let src: u8 = src.parse().with_context(|| format!("無効な行: '{s}'"))?;
+            let src = GroundRow::from_inner(src).ok_or_else(|| anyhow!("無効な行: '{s}'"))?;
+            Some(src)
+        };
+
+        let target = if target.is_empty() {
+            None
+        } else {
+            let mut chars = target.chars();
+            let dir = chars
+                .next()
+                .and_then(MoveDirection::from_char)
+                .ok_or_else(|| anyhow!("無効な移動方向: '{s}'"))?;
+            let dst: BlocksSquare = chars.as_str().parse()?;
+            Some((dir, dst))
+        };
+
+        ensure!(src.is_some() || target.is_some(), "空の着手記法: '{s}'");
+
+        Ok(Self { src, target })
+    }
+}
+
+impl std::fmt::Display for MoveNotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(src) = self.src {
+            write!(f, "r{}", src.to_inner())?;
+        }
+        if let Some((dir, dst)) = self.target {
+            write!(f, "{}{dst}", dir.to_char())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::ground::*;
+
+    use super::*;
+
+    #[test]
+    fn test_move_notation_io() {
+        let mv = Move::new(GROUND_ROW_9, BlocksSquare::F3, MoveDirection::Horizontal);
+        assert_eq!(mv.to_string(), "r9>F3");
+        assert_eq!("r9>F3".parse::<MoveNotation>().unwrap(), mv.into());
+
+        let mv = Move::new(GROUND_ROW_12, BlocksSquare::A1, MoveDirection::Vertical);
+        assert_eq!(mv.to_string(), "r12vA1");
+        assert_eq!("r12vA1".parse::<MoveNotation>().unwrap(), mv.into());
+
+        let cases = [
+            (
+                "r2",
+                MoveNotation {
+                    src: Some(GROUND_ROW_2),
+                    target: None,
+                },
+            ),
+            (
+                "11",
+                MoveNotation {
+                    src: Some(GROUND_ROW_11),
+                    target: None,
+                },
+            ),
+            (
+                "vC1",
+                MoveNotation {
+                    src: None,
+                    target: Some((MoveDirection::Vertical, BlocksSquare::C1)),
+                },
+            ),
+        ];
+        for (s, expect) in cases {
+            let notation: MoveNotation = s.parse().unwrap();
+            assert_eq!(notation, expect, "{s}");
+        }
+
+        for s in [
+            "", "r", "r13", "r0", "r9<F3", "r9>G3", "r9>F7", "r9>F", ">F3x", "rvA1",
+        ] {
+            assert!(s.parse::<MoveNotation>().is_err(), "{s}");
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::block::BlocksSquare;
+use crate::move_::Move;
+use crate::position::Position;
+
+/// 着手フィルタに渡される、着手とその結果。
+#[derive(Clone, Copy, Debug)]
+pub struct MoveContext<'a> {
+    /// 着手前の局面。
+    pub pos_before: &'a Position,
+    pub mv: Move,
+    /// 同時消し数。
+    pub erase_count: u8,
+    /// 最後にブロックが当たったマス。
+    pub sq_last: BlocksSquare,
+    /// 着手後の局面。
+    pub pos_after: &'a Position,
+}
+
+/// 着手を禁止するためのフィルタ。
+///
+/// ソルバーは全てのフィルタが許可した着手のみを探索し、`Solution::verify()` も同様にチェックする。
+/// `Fn(&MoveContext) -> bool` なクロージャもフィルタとして使える。
+pub trait MoveFilter: Send + Sync {
+    /// 着手を許可するなら `true` を返す。
+    fn allows(&self, ctx: &MoveContext<'_>) -> bool;
+
+    /// フィルタの名前 (`Solution::verify()` のエラーメッセージ用)。
+    fn name(&self) -> &str {
+        "(anonymous)"
+    }
+}
+
+impl<F> MoveFilter for F
+where
+    F: Fn(&MoveContext<'_>) -> bool + Send + Sync,
+{
+    fn allows(&self, ctx: &MoveContext<'_>) -> bool {
+        self(ctx)
+    }
+}
+
+/// 5 個以上の同時消しを禁止する (`SolverConfig::forbid_five`)。
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ForbidFive;
+
+impl MoveFilter for ForbidFive {
+    fn allows(&self, ctx: &MoveContext<'_>) -> bool {
+        ctx.erase_count < 5
+    }
+
+    fn name(&self) -> &str {
+        "forbid_five"
+    }
+}
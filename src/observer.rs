@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use crate::cost::Cost;
+use crate::solver::Solution;
+
+/// 探索の進捗を受け取るオブザーバ。
+///
+/// 並列探索の場合、各メソッドは複数のスレッドから呼ばれうる。
+/// その場合 `stats` などはそのメソッドを呼んだスレッドのみの値となる。
+pub trait SolverObserver: Send + Sync {
+    /// 解が改善されたときに呼ばれる。
+    /// (上位 k 個の解を求める場合、解が上位 k 個に入ったときに呼ばれる)
+    fn on_improved(&self, _solution: &Solution, _cost: Cost) {}
+
+    /// ノードを一定個数探索するごとに呼ばれる。
+    fn on_progress(&self, _stats: &SolverStats) {}
+
+    /// 探索がそれまでより深い局面に到達したときに呼ばれる (深さは初期局面からの手数)。
+    fn on_depth_reached(&self, _depth: usize) {}
+}
+
+/// 探索の統計情報。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SolverStats {
+    /// 展開したノード数。
+    pub node_count: u64,
+    /// コストの上限 (残りコストの下界を含む) により枝刈りしたノード数。
+    pub prune_bound_count: u64,
+    /// IDA* の閾値により次の反復に回したノード数。
+    pub prune_threshold_count: u64,
+    /// 置換表により枝刈りしたノード数。
+    pub prune_tt_count: u64,
+    /// `SolverConfig::end_constraints` を満たせないために枝刈りしたノード数。
+    pub prune_constraint_count: u64,
+    /// 着手フィルタ (`SolverConfig::forbid_five` を含む) により除外した着手数。
+    pub move_filtered_count: u64,
+    /// 合法手がなくなった局面のうち、解けていたものの数。
+    pub leaf_solved_count: u64,
+    /// 合法手がなくなった局面のうち、解けていなかった (ミス、あるいは `SolverConfig::end_constraints` を満たさない) ものの数。
+    pub leaf_miss_count: u64,
+    /// 到達した最大の深さ。
+    pub depth_max: usize,
+    /// 経過時間。
+    pub elapsed: Duration,
+}
+
+impl SolverStats {
+    /// 別のスレッドの統計情報を合算する (経過時間は長い方をとる)。
+    pub fn merge(&mut self, other: &Self) {
+        self.node_count += other.node_count;
+        self.prune_bound_count += other.prune_bound_count;
+        self.prune_threshold_count += other.prune_threshold_count;
+        self.prune_tt_count += other.prune_tt_count;
+        self.prune_constraint_count += other.prune_constraint_count;
+        self.move_filtered_count += other.move_filtered_count;
+        self.leaf_solved_count += other.leaf_solved_count;
+        self.leaf_miss_count += other.leaf_miss_count;
+        self.depth_max = self.depth_max.max(other.depth_max);
+        self.elapsed = self.elapsed.max(other.elapsed);
+    }
+}
@@ -1,7 +1,8 @@
 use anyhow::{anyhow, ensure, Context as _};
+use arrayvec::ArrayVec;
 
-use crate::block::{Block, Blocks};
-use crate::cost::{calc_hero_move_cost, calc_throw_cost, Cost};
+use crate::block::{Block, Blocks, BlocksSquare, BlocksUndo};
+use crate::cost::{Cost, CostModel, NesCostModel, ERASE_COUNT_MAX};
 use crate::ground::{GroundRow, GROUND_ROW_12};
 use crate::macros::assert_unchecked;
 use crate::move_::{Move, Moves};
@@ -45,6 +46,18 @@ impl Position {
         self.blocks.block_count()
     }
 
+    /// 局面を一意に表すキーを返す (置換表用)。
+    ///
+    /// 着手集合は盤面から一意に定まるのでキーには含めない。
+    pub fn key(&self) -> u128 {
+        let blocks = self.blocks.to_packed();
+        let hero_row = u128::from(self.hero_row.to_inner());
+        let block_holding = u128::from(self.block_holding.to_inner());
+        let move_count_remain = u128::from(self.move_count_remain);
+
+        blocks | (hero_row << 108) | (block_holding << 112) | (move_count_remain << 115)
+    }
+
     pub fn update_moves(&self, moves: &[Move]) -> Moves {
         self.blocks.update_moves(moves)
     }
@@ -57,29 +70,149 @@ impl Position {
         self.blocks.is_legal_move(mv, self.block_holding)
     }
 
-    /// 着手を行い、(結果, 自機の移動コスト, ブロック投げコスト, 同時消し数) を返す。
-    /// 着手は合法だと仮定している。
-    pub fn do_move(&self, mv: Move) -> (Self, Cost, Cost, u8) {
+    /// 着手を行い、(結果, 自機の移動コスト, ブロック投げコスト, 同時消し数, 最後にブロックが当たったマス) を返す。
+    /// 着手は合法だと仮定している。コストは `NesCostModel` に従う。
+    pub fn do_move(&self, mv: Move) -> (Self, Cost, Cost, u8, BlocksSquare) {
+        self.do_move_with(mv, &NesCostModel)
+    }
+
+    /// `do_move()` と同様だが、コストはモデル `cost_model` に従う。
+    pub fn do_move_with<M: CostModel + ?Sized>(
+        &self,
+        mv: Move,
+        cost_model: &M,
+    ) -> (Self, Cost, Cost, u8, BlocksSquare) {
+        let mut pos = self.clone();
+        let (_, cost_hero_move, cost_throw, erase_count, sq_last) =
+            pos.make_move_with(mv, cost_model);
+
+        (pos, cost_hero_move, cost_throw, erase_count, sq_last)
+    }
+
+    /// 着手をその場で行い、(取り消し情報, 自機の移動コスト, ブロック投げコスト, 同時消し数, 最後にブロックが当たったマス) を返す。
+    /// 着手は合法だと仮定している。コストは `NesCostModel` に従う。
+    ///
+    /// 取り消し情報を `unmake_move()` に渡すと着手前の局面に戻る。
+    pub fn make_move(&mut self, mv: Move) -> (PositionUndo, Cost, Cost, u8, BlocksSquare) {
+        self.make_move_with(mv, &NesCostModel)
+    }
+
+    /// `make_move()` と同様だが、コストはモデル `cost_model` に従う。
+    pub fn make_move_with<M: CostModel + ?Sized>(
+        &mut self,
+        mv: Move,
+        cost_model: &M,
+    ) -> (PositionUndo, Cost, Cost, u8, BlocksSquare) {
         unsafe { assert_unchecked!(self.move_count_remain > 0) }
 
-        let cost_hero_move = calc_hero_move_cost(self.hero_row, mv.src());
+        let cost_hero_move = cost_model.hero_move_cost(self.hero_row, mv.src());
 
-        let hero_row = mv.src();
-        let (blocks, block_holding, sq_last, erase_count) =
-            self.blocks.do_move(mv, self.block_holding);
-        let move_count_remain = self.move_count_remain - 1;
+        let (block_holding, sq_last, erase_count, blocks_undo) =
+            self.blocks.make_move(mv, self.block_holding);
 
-        let cost_throw = calc_throw_cost(mv.src(), sq_last);
+        let cost_throw = cost_model.throw_cost(mv.src(), sq_last);
 
-        let pos = Self {
-            hero_row,
-            blocks,
-            block_holding,
-            move_count_remain,
+        let undo = PositionUndo {
+            hero_row: self.hero_row,
+            block_holding: self.block_holding,
+            blocks: blocks_undo,
         };
 
-        (pos, cost_hero_move, cost_throw, erase_count)
+        self.hero_row = mv.src();
+        self.block_holding = block_holding;
+        self.move_count_remain -= 1;
+
+        (undo, cost_hero_move, cost_throw, erase_count, sq_last)
+    }
+
+    /// 着手の集合 `moves` (`update_moves()` で返されたもの) のうち、各合法手の結果を返す。
+    /// コストは `NesCostModel` に従う。残り手数が 0 の場合、空集合を返す。
+    pub fn preview_moves(&self, moves: &[Move]) -> Vec<MoveOutcome> {
+        self.preview_moves_with(moves, &NesCostModel)
+    }
+
+    /// `preview_moves()` と同様だが、コストはモデル `cost_model` に従う。
+    pub fn preview_moves_with<M: CostModel + ?Sized>(
+        &self,
+        moves: &[Move],
+        cost_model: &M,
+    ) -> Vec<MoveOutcome> {
+        if self.move_count_remain == 0 {
+            return vec![];
+        }
+
+        self.legal_moves(moves)
+            .into_iter()
+            .map(|mv| {
+                let (position, cost_hero_move, cost_throw, erase_count, sq_last) =
+                    self.do_move_with(mv, cost_model);
+
+                // sq_last までに通ったマスのブロックは全て消去され、
+                // その次のマスがあれば、そこで置換が起きている。
+                let mut path = Blocks::move_path(mv);
+                let mut erased = ArrayVec::new();
+                for sq in path.by_ref() {
+                    if self.blocks[sq].is_some() {
+                        erased.push(sq);
+                    }
+                    if sq == sq_last {
+                        break;
+                    }
+                }
+                let replaced = path.next();
+
+                MoveOutcome {
+                    mv,
+                    erased,
+                    replaced,
+                    block_holding: position.block_holding,
+                    sq_last,
+                    erase_count,
+                    cost_hero_move,
+                    cost_throw,
+                    position,
+                }
+            })
+            .collect()
     }
+
+    /// `make_move()` による着手を取り消す。
+    pub fn unmake_move(&mut self, undo: &PositionUndo) {
+        self.hero_row = undo.hero_row;
+        self.block_holding = undo.block_holding;
+        self.blocks.unmake_move(&undo.blocks);
+        self.move_count_remain += 1;
+    }
+}
+
+/// 着手の結果 (`Position::preview_moves()` が返す)。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MoveOutcome {
+    pub mv: Move,
+    /// 消去されたマス (着手前の盤面での位置)。消去された順に並ぶ。
+    pub erased: ArrayVec<BlocksSquare, { ERASE_COUNT_MAX as usize }>,
+    /// 置換されたマス (着手前の盤面での位置)。置換が起きなかった場合は `None`。
+    pub replaced: Option<BlocksSquare>,
+    /// 着手後の保持ブロック。
+    pub block_holding: Block,
+    /// 置換前に最後にブロックが通ったマス。
+    pub sq_last: BlocksSquare,
+    /// 同時消し数。
+    pub erase_count: u8,
+    /// 自機の移動コスト。
+    pub cost_hero_move: Cost,
+    /// ブロック投げコスト。
+    pub cost_throw: Cost,
+    /// 着手後の局面。
+    pub position: Position,
+}
+
+/// `Position::make_move()` の取り消し情報。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PositionUndo {
+    hero_row: GroundRow,
+    block_holding: Block,
+    blocks: BlocksUndo,
 }
 
 impl std::str::FromStr for Position {
@@ -144,9 +277,10 @@ mod tests {
     use indoc::indoc;
     use pretty_assertions::assert_eq;
 
-    use crate::block::BlocksSquare;
     use crate::ground::*;
     use crate::move_::MoveDirection;
+    use crate::solver::{SolverArgument, SolverConfig};
+    use crate::test_util::advance_problems;
 
     use super::*;
 
@@ -233,8 +367,187 @@ mod tests {
         for (before, mv, after) in cases {
             let before = parse_position(before);
             let after = parse_position(after);
-            let (after_actual, _, _, _) = before.do_move(mv);
+            let (after_actual, _, _, _, _) = before.do_move(mv);
             assert_eq!(after_actual, after);
         }
     }
+
+    #[test]
+    fn test_preview_moves() {
+        let pos = parse_position(indoc! {"
+            11 3 5
+            ......
+            ......
+            222222
+            333333
+            344444
+            311111
+        "});
+        let mv = Move::new(GROUND_ROW_10, BlocksSquare::F4, MoveDirection::Horizontal);
+        let outcomes = pos.preview_moves(&[mv]);
+        assert_eq!(outcomes.len(), 1);
+        let outcome = &outcomes[0];
+        assert_eq!(
+            outcome.erased.as_slice(),
+            [
+                BlocksSquare::F4,
+                BlocksSquare::E4,
+                BlocksSquare::D4,
+                BlocksSquare::C4,
+                BlocksSquare::B4,
+                BlocksSquare::A4,
+                BlocksSquare::A5,
+                BlocksSquare::A6,
+            ]
+        );
+        assert_eq!(outcome.replaced, None);
+        assert_eq!(outcome.block_holding, Block::Normal3);
+        assert_eq!(outcome.sq_last, BlocksSquare::A6);
+        assert_eq!(outcome.erase_count, 8);
+        assert_eq!(outcome.cost_hero_move, 16);
+        assert_eq!(outcome.cost_throw, 96);
+
+        let pos = parse_position(indoc! {"
+            1 1 5
+            ......
+            11....
+            11....
+            11....
+            11222.
+            312223
+        "});
+        let mv = Move::new(GROUND_ROW_6, BlocksSquare::A2, MoveDirection::Vertical);
+        // 保持ブロックで消せない位置への着手は含まれない。
+        let mv_illegal = Move::new(GROUND_ROW_12, BlocksSquare::F6, MoveDirection::Horizontal);
+        let outcomes = pos.preview_moves(&[mv, mv_illegal]);
+        assert_eq!(outcomes.len(), 1);
+        let outcome = &outcomes[0];
+        assert_eq!(
+            outcome.erased.as_slice(),
+            [
+                BlocksSquare::A2,
+                BlocksSquare::A3,
+                BlocksSquare::A4,
+                BlocksSquare::A5,
+            ]
+        );
+        assert_eq!(outcome.replaced, Some(BlocksSquare::A6));
+        assert_eq!(outcome.block_holding, Block::Normal3);
+        assert_eq!(outcome.sq_last, BlocksSquare::A5);
+        assert_eq!(outcome.erase_count, 4);
+        assert_eq!(outcome.cost_hero_move, 80);
+        assert_eq!(outcome.cost_throw, 111);
+        assert_eq!(outcome.position, pos.do_move(mv).0);
+    }
+
+    /// `Position::do_move()` の参照実装。`Blocks::do_move_naive()` を用い、`make_move()` とは独立に計算する。
+    fn do_move_naive(pos: &Position, mv: Move) -> (Position, Cost, Cost, u8, BlocksSquare) {
+        let (blocks, block_holding, sq_last, erase_count) =
+            pos.blocks.do_move_naive(mv, pos.block_holding);
+        let pos_nxt = Position {
+            hero_row: mv.src(),
+            blocks,
+            block_holding,
+            move_count_remain: pos.move_count_remain - 1,
+        };
+
+        (
+            pos_nxt,
+            NesCostModel.hero_move_cost(pos.hero_row, mv.src()),
+            NesCostModel.throw_cost(mv.src(), sq_last),
+            erase_count,
+            sq_last,
+        )
+    }
+
+    #[test]
+    fn test_make_unmake_move() {
+        /// `pos` から `depth` 手以内の全局面について、`make_move()` が参照実装 `do_move_naive()` と一致し、
+        /// `unmake_move()` で元の局面に戻ることを確かめる。
+        fn check(name: &str, pos: &mut Position, moves: &[Move], depth: usize) {
+            if depth == 0 || pos.move_count_remain() == 0 {
+                return;
+            }
+
+            for mv in pos.legal_moves(moves) {
+                let pos_before = pos.clone();
+                let expect = do_move_naive(pos, mv);
+
+                let (undo, cost_hero_move, cost_throw, erase_count, sq_last) = pos.make_move(mv);
+                assert_eq!(
+                    (
+                        pos.clone(),
+                        cost_hero_move,
+                        cost_throw,
+                        erase_count,
+                        sq_last
+                    ),
+                    expect,
+                    "{name}"
+                );
+
+                let moves_nxt = pos.update_moves(moves);
+                check(name, pos, &moves_nxt, depth - 1);
+                assert_eq!(*pos, expect.0, "{name}");
+
+                pos.unmake_move(&undo);
+                assert_eq!(*pos, pos_before, "{name}");
+            }
+        }
+
+        for (name, problem) in advance_problems() {
+            let arg = SolverArgument::new(&problem, &SolverConfig::default());
+
+            let mut pos = arg.position().clone();
+            check(&name, &mut pos, arg.moves(), 6);
+            assert_eq!(pos, *arg.position(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_preview_moves_advance() {
+        /// `pos` から `depth` 手以内の全局面について、`preview_moves()` の結果が参照実装 `do_move_naive()` と一致することを確かめる。
+        fn check(name: &str, pos: &Position, moves: &[Move], depth: usize) {
+            if depth == 0 {
+                return;
+            }
+
+            for outcome in pos.preview_moves(moves) {
+                let (pos_nxt, cost_hero_move, cost_throw, erase_count, sq_last) =
+                    do_move_naive(pos, outcome.mv);
+                assert_eq!(outcome.position, pos_nxt, "{name}");
+                assert_eq!(
+                    (
+                        outcome.block_holding,
+                        outcome.sq_last,
+                        outcome.erase_count,
+                        outcome.cost_hero_move,
+                        outcome.cost_throw
+                    ),
+                    (
+                        pos_nxt.block_holding(),
+                        sq_last,
+                        erase_count,
+                        cost_hero_move,
+                        cost_throw
+                    ),
+                    "{name}"
+                );
+                assert_eq!(outcome.erased.len(), usize::from(erase_count), "{name}");
+                // 置換されたマスのブロックが次の保持ブロックになる。
+                if let Some(sq) = outcome.replaced {
+                    assert_eq!(pos.blocks()[sq], Some(outcome.block_holding), "{name}");
+                }
+
+                let moves_nxt = pos_nxt.update_moves(moves);
+                check(name, &pos_nxt, &moves_nxt, depth - 1);
+            }
+        }
+
+        for (name, problem) in advance_problems() {
+            let arg = SolverArgument::new(&problem, &SolverConfig::default());
+
+            check(&name, arg.position(), arg.moves(), 4);
+        }
+    }
 }
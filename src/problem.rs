@@ -40,7 +40,7 @@ impl Problem {
         Self::validate_ground(&ground)?;
 
         ensure!(
-            !blocks.as_array().contains(&Some(Block::Wild)),
+            !blocks.to_array().contains(&Some(Block::Wild)),
             "ADVANCE モードの盤面にワイルドカードが含まれている"
         );
 
@@ -0,0 +1,67 @@
+use crate::cost::ERASE_COUNT_MAX;
+use crate::game_mode::GameMode;
+
+/// 得点を表す型。
+pub type Score = u32;
+
+/// 得点モデル。ソルバーと `Solution::verify_score()` はこれに従って得点を計算する。
+///
+/// 実機の得点を再現するモデルはまだないので、既定のモデルはない。
+/// 得点を用いる機能を使うには `SolverArgument::with_score_model()` で設定する必要がある。
+/// ソルバーは `throw_score_max()`, `clear_score_max()` を用いて枝刈りを行うので、
+/// これらは実際の得点を下回ってはならない。
+pub trait ScoreModel: Send + Sync {
+    /// 1 回の着手で `erase_count` 個のブロックを同時消ししたときの得点を求める。
+    fn throw_score(&self, erase_count: u8) -> Score;
+
+    /// 残りブロック数 `block_count`、残り手数 `move_count_remain` で面クリアした際のボーナスを求める。
+    fn clear_score(&self, game_mode: GameMode, block_count: u8, move_count_remain: u8) -> Score;
+
+    /// 1 回の着手による得点の最大値を求める。
+    ///
+    /// 既定の実装は全ての同時消し数を調べる。
+    fn throw_score_max(&self) -> Score {
+        (0..=ERASE_COUNT_MAX)
+            .map(|erase_count| self.throw_score(erase_count))
+            .max()
+            .unwrap()
+    }
+
+    /// 残り手数 `move_count_remain` 以下で面クリアした際のボーナスの最大値を求める。
+    ///
+    /// 既定の実装は、ボーナスが残りブロック数について単調減少、残り手数について単調増加だと仮定している。
+    fn clear_score_max(&self, game_mode: GameMode, move_count_remain: u8) -> Score {
+        self.clear_score(game_mode, 0, move_count_remain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::test_util::TestScoreModel;
+
+    use super::*;
+
+    #[test]
+    fn test_score_model_default_methods() {
+        assert_eq!(
+            TestScoreModel.throw_score_max(),
+            (0..=ERASE_COUNT_MAX)
+                .map(|erase_count| TestScoreModel.throw_score(erase_count))
+                .max()
+                .unwrap()
+        );
+
+        let advance = GameMode::Advance {
+            block_holding: Block::Wild,
+            move_count_remain: 13,
+        };
+        assert_eq!(
+            TestScoreModel.clear_score_max(advance, 4),
+            TestScoreModel.clear_score(advance, 0, 4)
+        );
+        assert!(
+            TestScoreModel.clear_score_max(advance, 4) >= TestScoreModel.clear_score(advance, 2, 4)
+        );
+    }
+}
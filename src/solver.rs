@@ -1,26 +1,232 @@
-use anyhow::{anyhow, ensure, Context as _};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, ensure, Context as _};
+use arrayvec::ArrayVec;
 use itertools::Itertools as _;
 use log::debug;
 
-use crate::block::{BlocksCol, BlocksRow, BlocksSquare, BLOCKS_COL_F, BLOCKS_ROW_1};
-use crate::cost::{calc_clear_cost, Cost, COST_INF};
+use crate::block::{Block, BlocksSquare};
+use crate::bounded_heap::BoundedHeap;
+use crate::cost::{calc_remaining_cost_lower_bound, Cost, CostModel, NesCostModel, COST_INF};
 use crate::game_mode::GameMode;
-use crate::ground::{GroundCol, GroundRow, GROUND_COL_A, GROUND_COL_H};
-use crate::move_::{Move, MoveDirection, Moves};
-use crate::position::Position;
+use crate::ground::{Ground, GroundRow};
+use crate::move_::{Move, MoveNotation, Moves};
+use crate::move_filter::{ForbidFive, MoveContext, MoveFilter};
+use crate::observer::{SolverObserver, SolverStats};
+use crate::position::{Position, PositionUndo};
 use crate::problem::Problem;
+use crate::score::{Score, ScoreModel};
+use crate::transposition::TranspositionTable;
 use crate::util::VecExt as _;
 
+/// 探索方式。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SearchMode {
+    /// 深さ優先の分枝限定法。
+    DepthFirst,
+    /// IDA*。残りコストの下界を用い、閾値を上げながら深さ優先探索を繰り返す。
+    IdaStar,
+    /// ビームサーチ。各深さで評価値の良い局面を最大 `width` 個だけ残して探索する。
+    ///
+    /// 最適解が得られるとは限らないが、手数の多い問題でも速く解を得られる。
+    /// 得られた解のコストを `SolverConfig::max_cost` に設定して厳密な探索を行うと、枝刈りが効きやすくなる。
+    /// 並列探索には対応しない。
+    Beam { width: usize },
+}
+
+/// 各局面において着手を試す順序。
+///
+/// 良い解を早く見つけるほど枝刈りが効きやすくなる。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MoveOrdering {
+    /// `SolverArgument` の着手集合の順 (下段から投げる手が先)。
+    Static,
+    /// 同時消し数が多い手を優先する。
+    EraseCount,
+    /// 自機の移動コストが小さい手を優先する。
+    HeroTravel,
+    /// 同じ深さで解の改善に寄与した回数が多い手を優先する (history heuristic)。
+    /// ただし、現在の最良解がその深さで選んだ手 (killer move) を最優先する。
+    History,
+}
+
+/// 解の評価指標 (`solve_problem_pareto()` 用)。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Metric {
+    /// コスト (小さいほど良い)。
+    Cost,
+    /// 着手数 (小さいほど良い)。
+    MoveCount,
+    /// 最終局面の残りブロック数 (小さいほど良い)。
+    BlockCount,
+    /// 得点 (大きいほど良い)。
+    Score,
+}
+
+/// 解の各種の評価指標。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SolutionMetrics {
+    pub cost: Cost,
+    /// 着手数。
+    pub move_count: usize,
+    /// 最終局面の残りブロック数。
+    pub block_count: u8,
+    /// 得点 (面クリアのボーナスを含む)。得点モデルが設定されていなければ `None`。
+    pub score: Option<Score>,
+}
+
+impl SolutionMetrics {
+    /// 評価指標 `metric` について `self` が `other` より良いなら `Less` を返す。
+    pub fn compare(&self, other: &Self, metric: Metric) -> std::cmp::Ordering {
+        match metric {
+            Metric::Cost => self.cost.cmp(&other.cost),
+            Metric::MoveCount => self.move_count.cmp(&other.move_count),
+            Metric::BlockCount => self.block_count.cmp(&other.block_count),
+            Metric::Score => other.score.cmp(&self.score),
+        }
+    }
+
+    /// `metrics` の全ての指標について `self` が `other` 以上に良く、かつ少なくとも 1 つで真に良いかどうかを返す。
+    pub fn dominates(&self, other: &Self, metrics: &[Metric]) -> bool {
+        self.weakly_dominates(other, metrics)
+            && metrics
+                .iter()
+                .any(|&metric| self.compare(other, metric).is_lt())
+    }
+
+    /// `metrics` の全ての指標について `self` が `other` 以上に良いかどうかを返す。
+    fn weakly_dominates(&self, other: &Self, metrics: &[Metric]) -> bool {
+        metrics
+            .iter()
+            .all(|&metric| self.compare(other, metric).is_le())
+    }
+}
+
+/// 解に対する追加の制約 (乱数調整用)。いずれも指定しなければ制約なし。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EndConstraints {
+    /// 最終局面の残りブロック数。
+    pub block_count: Option<u8>,
+    /// パーフェクト (残りブロック数 0) を要求するかどうか。
+    pub perfect: bool,
+    /// 最終局面の保持ブロック。
+    pub block_holding: Option<Block>,
+    /// 最終局面の自機位置。
+    pub hero_row: Option<GroundRow>,
+    /// 着手数の最小値。
+    pub move_count_min: Option<u8>,
+    /// 着手数の最大値。
+    pub move_count_max: Option<u8>,
+}
+
+impl EndConstraints {
+    /// 最終局面 `pos` と着手数 `move_count` が制約を満たすかどうかを返す。
+    pub fn is_satisfied(&self, pos: &Position, move_count: usize) -> bool {
+        self.block_count
+            .is_none_or(|count| pos.block_count() == count)
+            && (!self.perfect || pos.block_count() == 0)
+            && self
+                .block_holding
+                .is_none_or(|block| pos.block_holding() == block)
+            && self.hero_row.is_none_or(|row| pos.hero_row() == row)
+            && self
+                .move_count_min
+                .is_none_or(|min| move_count >= usize::from(min))
+            && self
+                .move_count_max
+                .is_none_or(|max| move_count <= usize::from(max))
+    }
+
+    /// `is_satisfied()` と同様だが、満たさない制約をエラーとして返す。
+    pub fn check(&self, pos: &Position, move_count: usize) -> anyhow::Result<()> {
+        if let Some(count) = self.block_count {
+            ensure!(
+                pos.block_count() == count,
+                "最終局面の残りブロック数が制約を満たさない (制約: {count}, 実際: {})",
+                pos.block_count()
+            );
+        }
+        if self.perfect {
+            ensure!(
+                pos.block_count() == 0,
+                "パーフェクトでない (残りブロック数: {})",
+                pos.block_count()
+            );
+        }
+        if let Some(block) = self.block_holding {
+            ensure!(
+                pos.block_holding() == block,
+                "最終局面の保持ブロックが制約を満たさない (制約: {}, 実際: {})",
+                block.to_inner(),
+                pos.block_holding().to_inner()
+            );
+        }
+        if let Some(row) = self.hero_row {
+            ensure!(
+                pos.hero_row() == row,
+                "最終局面の自機位置が制約を満たさない (制約: {}, 実際: {})",
+                row.to_inner(),
+                pos.hero_row().to_inner()
+            );
+        }
+        if let Some(min) = self.move_count_min {
+            ensure!(
+                move_count >= usize::from(min),
+                "着手数が最小値を下回る (制約: {min} 以上, 実際: {move_count})"
+            );
+        }
+        if let Some(max) = self.move_count_max {
+            ensure!(
+                move_count <= usize::from(max),
+                "着手数が最大値を上回る (制約: {max} 以下, 実際: {move_count})"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// 残りブロック数が `block_count` の局面から、制約を満たす最終局面に到達しうるかどうかを返す
+    /// (ブロックは増えないことを用いる)。
+    fn is_reachable(&self, block_count: u8) -> bool {
+        self.block_count.is_none_or(|count| block_count >= count)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SolverConfig {
     /// 最終面かどうか。
     pub last_stage: bool,
     /// 5 個以上の同時消しを禁止するかどうか。
+    ///
+    /// `SolverArgument::new()` において、着手フィルタ `ForbidFive` として登録される。
     pub forbid_five: bool,
     /// just clear を禁止するかどうか(乱数調整のために必要なことがある)。
     pub forbid_just: bool,
     /// 最大コスト (枝刈り用)。
     pub max_cost: Cost,
+    /// 解に対する追加の制約。
+    pub end_constraints: EndConstraints,
+    /// 置換表のエントリ数 (0 なら置換表を使わない)。2 の冪に切り上げられる。
+    ///
+    /// 1 エントリあたり 22 バイトを使う。長い ADVANCE の面では大きくすると速くなることがある。
+    pub tt_capacity: usize,
+    /// 探索方式。
+    pub search_mode: SearchMode,
+    /// 着手を試す順序。
+    pub move_ordering: MoveOrdering,
+    /// 探索スレッド数 (`parallel` feature が有効な場合のみ意味を持つ)。
+    ///
+    /// 2 以上の場合、置換表の容量は各スレッドに等分される。
+    pub thread_count: usize,
+    /// 探索するノード数の上限。これを超えたら探索を打ち切る。
+    ///
+    /// 並列探索の場合、全スレッドの合計ノード数に対する上限となる。
+    /// ただし、スレッド間での集計の粒度のため、わずかに超えることがある。
+    pub max_nodes: u64,
+    /// 探索時間の上限。これを超えたら探索を打ち切る。
+    pub time_limit: Option<Duration>,
 }
 
 impl Default for SolverConfig {
@@ -30,16 +236,183 @@ impl Default for SolverConfig {
             forbid_five: false,
             forbid_just: false,
             max_cost: COST_INF,
+            end_constraints: EndConstraints::default(),
+            tt_capacity: 1 << 16,
+            search_mode: SearchMode::DepthFirst,
+            move_ordering: MoveOrdering::Static,
+            thread_count: 1,
+            max_nodes: u64::MAX,
+            time_limit: None,
+        }
+    }
+}
+
+/// 探索を外部から中断するためのトークン。
+/// clone したトークンは状態を共有する。
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 探索の中断を要求する。
+    pub fn cancel(&self) {
+        self.0.store(true, AtomicOrdering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(AtomicOrdering::Relaxed)
+    }
+}
+
+/// 探索木の部分木の根 (途中の局面から探索する場合や、並列探索のタスクに用いる)。
+#[derive(Clone, Debug)]
+struct Subtree {
+    /// 初期局面からこの局面までの手順。
+    solution: Vec<Move>,
+    pos: Position,
+    moves: Moves,
+    /// この局面までのコスト (最終手のブロック投げコストを含む)。
+    cost: Cost,
+    /// 最終手のブロック投げコスト。
+    cost_last_throw: Cost,
+}
+
+impl Subtree {
+    /// 初期局面を根とする部分木 (探索木全体) を返す。
+    fn root(arg: &SolverArgument) -> Self {
+        Self {
+            solution: vec![],
+            pos: arg.pos.clone(),
+            moves: arg.moves.clone(),
+            cost: 0,
+            cost_last_throw: 0,
+        }
+    }
+
+    /// 着手 `mv` (`i` 番目の着手) を行い、この部分木を子局面を根とするものに置き換える。同時消し数を返す。
+    /// 着手フィルタにより禁止されている場合はエラーを返す (着手の合法性はチェックしない)。
+    fn advance(&mut self, arg: &SolverArgument, i: usize, mv: Move) -> anyhow::Result<u8> {
+        let (pos, cost_hero_move, cost_throw, erase_count, sq_last) =
+            self.pos.do_move_with(mv, arg.cost_model());
+        let ctx = MoveContext {
+            pos_before: &self.pos,
+            mv,
+            erase_count,
+            sq_last,
+            pos_after: &pos,
+        };
+        if let Some(filter) = find_rejecting_filter(&arg.move_filters, &ctx) {
+            bail!(
+                "{i} 番目の着手が着手フィルタ '{}' により禁止されている: {mv:?}",
+                filter.name()
+            );
+        }
+
+        self.moves = pos.update_moves(&self.moves);
+        self.pos = pos;
+        self.solution.push(mv);
+        self.cost += cost_hero_move + cost_throw;
+        self.cost_last_throw = cost_throw;
+
+        Ok(erase_count)
+    }
+
+    /// 着手 `mv` による子局面を根とする部分木を返す。着手フィルタにより禁止されている場合は `None` を返す。
+    fn child(&self, arg: &SolverArgument, mv: Move) -> Option<Self> {
+        let (pos, cost_hero_move, cost_throw, erase_count, sq_last) =
+            self.pos.do_move_with(mv, arg.cost_model());
+        let ctx = MoveContext {
+            pos_before: &self.pos,
+            mv,
+            erase_count,
+            sq_last,
+            pos_after: &pos,
+        };
+        if find_rejecting_filter(&arg.move_filters, &ctx).is_some() {
+            return None;
         }
+
+        Some(Self {
+            solution: [self.solution.as_slice(), &[mv]].concat(),
+            moves: pos.update_moves(&self.moves),
+            pos,
+            cost: self.cost + cost_hero_move + cost_throw,
+            cost_last_throw: cost_throw,
+        })
+    }
+}
+
+/// `SolverObserver` への参照 (`Debug` を実装するためのラッパー)。
+#[derive(Clone)]
+struct ObserverRef(Arc<dyn SolverObserver>);
+
+impl std::fmt::Debug for ObserverRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ObserverRef(..)")
+    }
+}
+
+/// `CostModel` への参照 (`Debug` を実装するためのラッパー)。
+#[derive(Clone)]
+struct CostModelRef(Arc<dyn CostModel>);
+
+impl Default for CostModelRef {
+    fn default() -> Self {
+        Self(Arc::new(NesCostModel))
+    }
+}
+
+impl std::fmt::Debug for CostModelRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CostModelRef(..)")
+    }
+}
+
+/// `ScoreModel` への参照 (`Debug` を実装するためのラッパー)。
+#[derive(Clone)]
+struct ScoreModelRef(Arc<dyn ScoreModel>);
+
+impl std::fmt::Debug for ScoreModelRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ScoreModelRef(..)")
+    }
+}
+
+/// `MoveFilter` への参照 (`Debug` を実装するためのラッパー)。
+#[derive(Clone)]
+struct MoveFilterRef(Arc<dyn MoveFilter>);
+
+impl std::fmt::Debug for MoveFilterRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MoveFilterRef({})", self.0.name())
     }
 }
 
+/// 着手を禁止するフィルタがあればそれを返す。
+fn find_rejecting_filter<'a>(
+    filters: &'a [MoveFilterRef],
+    ctx: &MoveContext<'_>,
+) -> Option<&'a dyn MoveFilter> {
+    filters
+        .iter()
+        .map(|filter| filter.0.as_ref())
+        .find(|filter| !filter.allows(ctx))
+}
+
 #[derive(Debug)]
 pub struct SolverArgument {
     game_mode: GameMode,
     pos: Position,
     moves: Moves,
     config: SolverConfig,
+    cancel_token: Option<CancelToken>,
+    observer: Option<ObserverRef>,
+    move_filters: Vec<MoveFilterRef>,
+    cost_model: CostModelRef,
+    score_model: Option<ScoreModelRef>,
 }
 
 impl SolverArgument {
@@ -53,6 +426,20 @@ impl SolverArgument {
             Position::new(blocks, block_holding, move_count_remain)
         };
 
+        Self::from_position(problem.ground(), game_mode, pos, config)
+    }
+
+    /// 面の途中の局面 `pos` から解くための引数を作る。
+    /// 着手集合は `new()` と同様に地形 `ground` から求める。
+    ///
+    /// `ground` は `Problem` と同じ制約を満たし、`pos` の盤面はその地形上で到達可能なものと仮定している。
+    /// `pos` の残り手数が 0 の場合、合法手が残っていれば面クリアできない局面として扱われる。
+    pub fn from_position(
+        ground: &Ground,
+        game_mode: GameMode,
+        pos: Position,
+        config: &SolverConfig,
+    ) -> Self {
         // 着手集合の初期値を求める。
         // このとき、下段から投げる手を優先するようにする。
         // (面開始時に自機は最下段にいるので、この方が最適解に早く到達しやすいだろうという予想)
@@ -60,49 +447,72 @@ impl SolverArgument {
             .into_iter()
             .rev()
             .filter_map(|grow| {
-                if grow.is_blocks_area() {
-                    // 下 6 行の場合、壁/パイプがなければ横に投げる着手が存在し、
-                    // さもなくば着手は存在しない(壁/パイプは左下 5x6 内には存在しないことに注意)。
-                    GroundCol::all()
-                        .into_iter()
-                        .all(|gcol| problem.ground()[(gcol, grow)].is_none())
-                        .then(|| {
-                            let brow = BlocksRow::try_from(grow).unwrap();
-                            let dst = BlocksSquare::new(BLOCKS_COL_F, brow);
-                            Move::new(grow, dst, MoveDirection::Horizontal)
-                        })
-                } else {
-                    // 上 6 行の場合、壁/パイプに当たってから下に落ちる。
-                    // まず最初に当たる壁/パイプの列を求める。
-                    let gcol = GroundCol::all()
-                        .into_iter()
-                        .rev()
-                        .find(|&gcol| problem.ground()[(gcol, grow)].is_some());
-                    // 最初に当たった壁/パイプの 1 つ右の列に落ちることになる。
-                    // 壁/パイプに当たらないなら A 列に落ちる。
-                    // H 列の壁/パイプに当たった場合、H 列に落ちるものとみなす。
-                    let gcol =
-                        gcol.map_or(GROUND_COL_A, |gcol| gcol.next().unwrap_or(GROUND_COL_H));
-                    // 左 6 列に落ちる場合のみ有効な着手とする。
-                    BlocksCol::try_from(gcol).ok().map(|bcol| {
-                        let dst = BlocksSquare::new(bcol, BLOCKS_ROW_1);
-                        Move::new(grow, dst, MoveDirection::Vertical)
+                // 投げたブロックが最初にブロック領域に入るマスを対象とする着手が存在する。
+                // ブロック領域に入らないなら着手は存在しない。
+                ground
+                    .throw_trajectory(grow)
+                    .find(|(gsq, _)| gsq.is_blocks_area())
+                    .map(|(gsq, mv_dir)| {
+                        let dst = BlocksSquare::try_from(gsq).unwrap();
+                        Move::new(grow, dst, mv_dir)
                     })
-                }
             })
             .collect();
 
         // 着手集合を局面に合わせて更新。
         let moves = pos.update_moves(&moves);
 
+        let mut move_filters = vec![];
+        if config.forbid_five {
+            move_filters.push(MoveFilterRef(Arc::new(ForbidFive)));
+        }
+
         Self {
             game_mode,
             pos,
             moves,
             config: config.clone(),
+            cancel_token: None,
+            observer: None,
+            move_filters,
+            cost_model: CostModelRef::default(),
+            score_model: None,
         }
     }
 
+    /// 探索を中断するためのトークンを設定する。
+    pub fn with_cancel_token(mut self, cancel_token: CancelToken) -> Self {
+        self.cancel_token = Some(cancel_token);
+        self
+    }
+
+    /// 探索の進捗を受け取るオブザーバを設定する。
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver>) -> Self {
+        self.observer = Some(ObserverRef(observer));
+        self
+    }
+
+    /// 着手フィルタを追加する。全てのフィルタが許可した着手のみが探索される。
+    pub fn with_move_filter(mut self, filter: Arc<dyn MoveFilter>) -> Self {
+        self.move_filters.push(MoveFilterRef(filter));
+        self
+    }
+
+    /// コストモデルを設定する (既定は `NesCostModel`)。
+    pub fn with_cost_model(mut self, cost_model: Arc<dyn CostModel>) -> Self {
+        self.cost_model = CostModelRef(cost_model);
+        self
+    }
+
+    /// 得点モデルを設定する。
+    ///
+    /// 既定では設定されていない。得点を用いる機能 (`Metric::Score` を含む `solve_problem_pareto()`、
+    /// `Solution::verify_score()`) には設定が必要。
+    pub fn with_score_model(mut self, score_model: Arc<dyn ScoreModel>) -> Self {
+        self.score_model = Some(ScoreModelRef(score_model));
+        self
+    }
+
     pub fn game_mode(&self) -> GameMode {
         self.game_mode
     }
@@ -118,6 +528,34 @@ impl SolverArgument {
     pub fn config(&self) -> &SolverConfig {
         &self.config
     }
+
+    pub fn cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel_token.as_ref()
+    }
+
+    pub fn observer(&self) -> Option<&dyn SolverObserver> {
+        self.observer.as_ref().map(|observer| observer.0.as_ref())
+    }
+
+    pub fn move_filters(&self) -> impl Iterator<Item = &dyn MoveFilter> + '_ {
+        self.move_filters.iter().map(|filter| filter.0.as_ref())
+    }
+
+    pub fn cost_model(&self) -> &dyn CostModel {
+        self.cost_model.0.as_ref()
+    }
+
+    pub fn score_model(&self) -> Option<&dyn ScoreModel> {
+        self.score_model
+            .as_ref()
+            .map(|score_model| score_model.0.as_ref())
+    }
+
+    /// 同時消し数 `erase_count` の着手による得点を求める。得点モデルが設定されていなければ 0 とする。
+    fn throw_score(&self, erase_count: u8) -> Score {
+        self.score_model()
+            .map_or(0, |score_model| score_model.throw_score(erase_count))
+    }
 }
 
 #[derive(Debug)]
@@ -142,40 +580,33 @@ impl Solution {
     }
 
     pub fn verify(&self, arg: &SolverArgument) -> anyhow::Result<Cost> {
-        let mut pos = arg.pos.clone();
-        let mut moves = arg.moves.clone();
-        let mut cost_total = 0;
-
-        for (i, &src) in self.srcs().iter().enumerate() {
-            ensure!(
-                pos.move_count_remain() > 0,
-                "{i} 番目の着手前に残り手数が尽きた"
-            );
+        self.verify_metrics(arg).map(|metrics| metrics.cost)
+    }
 
-            let mv = moves
-                .iter()
-                .copied()
-                .find(|mv| mv.src() == src)
-                .ok_or_else(|| anyhow!("{i} 番目の着手が不正: {src:?}"))?;
-            ensure!(pos.is_legal_move(mv), "{i} 番目の着手が不正: {mv:?}");
-            let (pos_nxt, cost_hero_move, cost_throw, erase_count) = pos.do_move(mv);
-            if arg.config.forbid_five {
-                ensure!(
-                    erase_count < 5,
-                    "{i} 番目の着手で 5 個以上の同時消しが起こった: {mv:?}"
-                );
-            }
+    /// `verify()` と同様に解をチェックし、得点 (面クリアのボーナスを含む) を返す。
+    /// 得点モデルが設定されていなければエラーを返す。
+    pub fn verify_score(&self, arg: &SolverArgument) -> anyhow::Result<Score> {
+        self.verify_metrics(arg)?
+            .score
+            .ok_or_else(|| anyhow!("得点モデルが設定されていない"))
+    }
 
-            pos = pos_nxt;
-            moves = pos.update_moves(&moves);
+    /// `verify()` と同様に解をチェックし、各種の評価指標を返す。
+    pub fn verify_metrics(&self, arg: &SolverArgument) -> anyhow::Result<SolutionMetrics> {
+        let (
+            Subtree {
+                pos,
+                moves,
+                cost: mut cost_total,
+                cost_last_throw,
+                ..
+            },
+            score_throw,
+        ) = self.replay(arg)?;
 
-            // コストを加算する。
-            // 最終面の場合、最終手のブロック投げコストは 1 とみなす。
-            if arg.config.last_stage && i == self.len() - 1 {
-                cost_total += cost_hero_move + 1;
-            } else {
-                cost_total += cost_hero_move + cost_throw;
-            }
+        // 最終面の場合、最終手のブロック投げコストは 1 とみなす。
+        if arg.config.last_stage && !self.is_empty() {
+            cost_total = cost_total - cost_last_throw + 1;
         }
 
         ensure!(
@@ -195,9 +626,64 @@ impl Solution {
             "最後の局面が解けていない:\n{pos}"
         );
 
-        cost_total += calc_clear_cost(arg.game_mode, &pos, arg.config.last_stage);
+        arg.config
+            .end_constraints
+            .check(&pos, self.len())
+            .context("解が制約を満たさない")?;
+
+        cost_total +=
+            arg.cost_model()
+                .clear_cost(arg.game_mode, pos.block_count(), arg.config.last_stage);
+
+        let score_total = arg.score_model().map(|score_model| {
+            score_throw
+                + score_model.clear_score(arg.game_mode, pos.block_count(), pos.move_count_remain())
+        });
+
+        Ok(SolutionMetrics {
+            cost: cost_total,
+            move_count: self.len(),
+            block_count: pos.block_count(),
+            score: score_total,
+        })
+    }
+
+    /// 初期局面から手順を再生し、各着手を返す。各着手の正当性をチェックする。
+    pub fn to_moves(&self, arg: &SolverArgument) -> anyhow::Result<Vec<Move>> {
+        self.replay(arg).map(|(res, _)| res.solution)
+    }
+
+    /// 解を着手記法 (例: "r9>F3,r2vA1") で表した文字列を返す。各着手の正当性をチェックする。
+    pub fn to_notation(&self, arg: &SolverArgument) -> anyhow::Result<String> {
+        Ok(self.to_moves(arg)?.iter().join(","))
+    }
+
+    /// 初期局面から手順を再生し、その結果と得点を返す。各着手の正当性をチェックする。
+    /// 返り値のコストは、最終面であっても最終手のブロック投げコストをそのまま含む。
+    /// また、返り値の得点は面クリアのボーナスを含まない。
+    fn replay(&self, arg: &SolverArgument) -> anyhow::Result<(Subtree, Score)> {
+        let mut res = Subtree::root(arg);
+        let mut score = 0;
+
+        for (i, &src) in self.srcs().iter().enumerate() {
+            let pos = &res.pos;
+            ensure!(
+                pos.move_count_remain() > 0,
+                "{i} 番目の着手前に残り手数が尽きた"
+            );
+
+            let mv = res
+                .moves
+                .iter()
+                .copied()
+                .find(|mv| mv.src() == src)
+                .ok_or_else(|| anyhow!("{i} 番目の着手が不正: {src:?}"))?;
+            ensure!(pos.is_legal_move(mv), "{i} 番目の着手が不正: {mv:?}");
+            let erase_count = res.advance(arg, i, mv)?;
+            score += arg.throw_score(erase_count);
+        }
 
-        Ok(cost_total)
+        Ok((res, score))
     }
 }
 
@@ -234,93 +720,2438 @@ impl std::fmt::Display for Solution {
     }
 }
 
-pub fn solve_problem(arg: &SolverArgument) -> Option<(Solution, Cost)> {
-    let mut solver = Solver::new(arg.game_mode, arg.config.clone());
+/// 着手記法 (例: "r9>F3,r2vA1") で表された手順を初期局面から解釈し、着手の列を返す。
+///
+/// 各トークンは `MoveNotation` の形式で、その時点の合法手のうちちょうど 1 つに該当しなければならない。
+/// また、`Solution::verify()` と同様に各着手の正当性 (着手フィルタを含む) をチェックする。
+pub fn parse_move_notation(arg: &SolverArgument, s: &str) -> anyhow::Result<Vec<Move>> {
+    let mut res = Subtree::root(arg);
 
-    solver.solve(&arg.pos, &arg.moves, 0, 0);
+    for (i, token) in s.split(',').enumerate() {
+        let notation: MoveNotation = token
+            .parse()
+            .with_context(|| format!("{i} 番目の着手記法が不正"))?;
+        ensure!(
+            res.pos.move_count_remain() > 0,
+            "{i} 番目の着手前に残り手数が尽きた"
+        );
 
-    solver.best_solution.map(|moves| {
-        let solution = Solution::from_moves(&moves);
-        (solution, solver.best_cost)
-    })
+        let candidates: Moves = res
+            .pos
+            .legal_moves(&res.moves)
+            .into_iter()
+            .filter(|&mv| notation.matches(mv))
+            .collect();
+        let mv = match candidates.as_slice() {
+            &[mv] => mv,
+            [] => bail!("{i} 番目の着手に該当する合法手がない: '{token}'"),
+            _ => bail!(
+                "{i} 番目の着手が曖昧: '{token}' (候補: {})",
+                candidates.iter().join(",")
+            ),
+        };
+
+        res.advance(arg, i, mv)?;
+    }
+
+    Ok(res.solution)
+}
+
+/// 探索が打ち切られた理由。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InterruptReason {
+    /// ノード数の上限に達した。
+    NodeLimit,
+    /// 探索時間の上限に達した。
+    TimeLimit,
+    /// `CancelToken` により中断された。
+    Cancelled,
 }
 
+/// 探索の終了状態。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SolveStatus {
+    /// 探索を完了した。解が見つかっていればそれは最適解であり、さもなくば解は存在しない。
+    /// (ただし、ビームサーチの場合は単に探索を終えたことを表す)
+    Completed,
+    /// 探索が打ち切られた。解が見つかっていても最適とは限らない。
+    Interrupted(InterruptReason),
+}
+
+/// 探索結果。
 #[derive(Debug)]
-struct Solver {
-    game_mode: GameMode,
-    config: SolverConfig,
+pub struct SolveOutcome {
+    /// 見つかった最良の解とそのコスト。
+    pub best: Option<(Solution, Cost)>,
+    pub status: SolveStatus,
+    /// 探索の統計情報。
+    pub stats: SolverStats,
+}
 
-    best_solution: Option<Vec<Move>>,
-    best_cost: Cost,
-    cur_solution: Vec<Move>,
+impl SolveOutcome {
+    /// 最適解が得られた (あるいは解が存在しないことが示された) かどうかを返す。
+    pub fn is_completed(&self) -> bool {
+        self.status == SolveStatus::Completed
+    }
 }
 
-impl Solver {
-    fn new(game_mode: GameMode, config: SolverConfig) -> Self {
-        let best_cost = config.max_cost + 1;
+pub fn solve_problem(arg: &SolverArgument) -> Option<(Solution, Cost)> {
+    solve_problem_outcome(arg).best
+}
 
-        Self {
-            game_mode,
-            config,
+/// `solve_problem()` と同様だが、探索が打ち切られたかどうかも返す。
+///
+/// 探索は `SolverConfig::max_nodes`, `SolverConfig::time_limit`, `SolverArgument::cancel_token()`
+/// のいずれかにより打ち切られうる。その場合、それまでに見つかった最良の解を返す。
+pub fn solve_problem_outcome(arg: &SolverArgument) -> SolveOutcome {
+    solve_subtree(arg, &arg.config, &Subtree::root(arg))
+}
 
-            best_solution: None,
-            best_cost,
-            cur_solution: vec![],
-        }
+/// 初期局面から手順 `prefix` を指した後の最適な続きを求め、`prefix` と合わせた解を返す。
+///
+/// `prefix` は `Solution::verify()` と同様にチェックされ、不正ならエラーを返す。
+/// 返される解のコストは `prefix` の分も含む。
+pub fn solve_problem_after_prefix(
+    arg: &SolverArgument,
+    prefix: &Solution,
+) -> anyhow::Result<SolveOutcome> {
+    let (subtree, _) = prefix.replay(arg).context("手順の前半が不正")?;
+
+    Ok(solve_subtree(arg, &arg.config, &subtree))
+}
+
+/// `explain_solution()` が返す、解の各着手の評価。
+#[derive(Debug)]
+pub struct MoveExplanation {
+    /// 実際の着手 (投げる位置)。
+    pub played: GroundRow,
+    /// 着手前の局面における各合法手 (着手フィルタで禁止されたものを除く) と、
+    /// その手を指した後に最適に続けた場合の総コスト (解がなければ `None`)。
+    pub alternatives: Vec<(GroundRow, Option<Cost>)>,
+    /// 着手前の局面から最適に続けた場合の総コスト。
+    pub cost_best: Cost,
+    /// 実際の着手の後に最適に続けた場合の総コスト。
+    pub cost_played: Cost,
+    /// 全ての探索を完了したかどうか。`false` ならコストは最適とは限らない。
+    pub is_exact: bool,
+}
+
+impl MoveExplanation {
+    /// 実際の着手によって失ったコスト (最適な着手なら 0)。
+    pub fn regret(&self) -> Cost {
+        self.cost_played - self.cost_best
     }
+}
 
-    fn solve(&mut self, pos: &Position, moves: &[Move], cost: Cost, cost_last_throw: Cost) {
-        // 現局面が解けていると仮定したときの総コストを求める。
-        let cost_solved = if self.config.last_stage {
-            // 最終面の場合、最終手のブロック投げコストは 1 (A を押して放置するだけ) とみなす。
-            // また、面クリア時の演出コストを加算しない。
-            cost - cost_last_throw + 1
-        } else {
-            // 最終面でない場合、面クリア時の演出コストを加算する。
-            cost + calc_clear_cost(self.game_mode, pos, self.config.last_stage)
-        };
+/// 解 `solution` の各着手について、その時点での全ての合法手の最適な続きのコストを求め、
+/// 実際の着手による損失 (regret) を評価する。
+///
+/// コストは全て初期局面からの総コスト。各着手の regret の和は、解のコストと最適解のコストの差に等しい。
+/// `solution` は `Solution::verify()` と同様にチェックされ、不正ならエラーを返す。
+pub fn explain_solution(
+    arg: &SolverArgument,
+    solution: &Solution,
+) -> anyhow::Result<Vec<MoveExplanation>> {
+    let cost_solution = solution.verify(arg).context("解が不正")?;
 
-        // 現局面が解けていると仮定してもコストが改善しないなら枝刈り。
-        if cost_solved >= self.best_cost {
-            return;
-        }
+    // 探索は (合法手の数) × (解の手数) 回行われ、いずれも部分木のみを探索するので、置換表は小さくてよい。
+    let config = SolverConfig {
+        tt_capacity: arg.config.tt_capacity.min(EXPLAIN_TT_CAPACITY),
+        ..arg.config.clone()
+    };
 
-        // 合法手の集合を求める。
-        let moves_legal = pos.legal_moves(moves);
+    let mut res = Vec::with_capacity(solution.len());
+    let mut subtree = Subtree::root(arg);
 
-        // 合法手がない場合、解けている/ミスのいずれかである。どちらにせよここで戻る。
-        // (just clear で、かつそれが禁止されているなら単にミスとみなす)
-        if moves_legal.is_empty() {
-            // 解けているなら解を更新(コストが改善しないケースは事前に枝刈りされている)。
-            let solved = if self.config.forbid_just {
-                pos.block_count() < self.game_mode.block_count_target()
-            } else {
-                pos.block_count() <= self.game_mode.block_count_target()
+    for &played in solution.srcs() {
+        let mut alternatives = vec![];
+        let mut is_exact = true;
+        let mut subtree_nxt = None;
+        for mv in subtree.pos.legal_moves(&subtree.moves) {
+            let Some(child) = subtree.child(arg, mv) else {
+                continue;
             };
-            if solved {
-                self.best_solution = Some(self.cur_solution.clone());
-                self.best_cost = cost_solved;
-                debug!(
-                    "improved: {} {}",
-                    self.best_cost,
-                    Solution::from_moves(&self.cur_solution)
-                );
+            let outcome = solve_subtree(arg, &config, &child);
+            is_exact &= outcome.is_completed();
+            alternatives.push((mv.src(), outcome.best.map(|(_, cost)| cost)));
+            if mv.src() == played {
+                subtree_nxt = Some(child);
             }
-            return;
         }
 
-        for mv in moves_legal {
-            let (pos_nxt, cost_hero_move, cost_throw, erase_count) = pos.do_move(mv);
-            if self.config.forbid_five && erase_count >= 5 {
-                continue;
-            }
+        // 実際の解の続きも解なので、探索が打ち切られた場合でもそのコストを上限とする。
+        let cost_played = alternatives
+            .iter()
+            .find(|&&(src, _)| src == played)
+            .and_then(|&(_, cost)| cost)
+            .map_or(cost_solution, |cost| cost.min(cost_solution));
+        let cost_best = alternatives
+            .iter()
+            .filter_map(|&(_, cost)| cost)
+            .chain([cost_played])
+            .min()
+            .unwrap();
+
+        res.push(MoveExplanation {
+            played,
+            alternatives,
+            cost_best,
+            cost_played,
+            is_exact,
+        });
+
+        subtree = subtree_nxt.expect("verify 済みの解の着手は合法なはず");
+    }
+
+    Ok(res)
+}
 
-            let moves_nxt = pos_nxt.update_moves(moves);
-            let cost_nxt = cost + cost_hero_move + cost_throw;
+/// 設定 `config` で部分木 `subtree` 内の解を探索する (`arg` の設定は用いない)。
+fn solve_subtree(arg: &SolverArgument, config: &SolverConfig, subtree: &Subtree) -> SolveOutcome {
+    #[cfg(feature = "parallel")]
+    if config.thread_count > 1 && !matches!(config.search_mode, SearchMode::Beam { .. }) {
+        return solve_problem_parallel(arg, config, subtree);
+    }
+
+    let mut solver = Solver::with_argument(arg, config.clone());
+
+    match config.search_mode {
+        SearchMode::DepthFirst => solver.solve_subtree(subtree),
+        SearchMode::IdaStar => solver.solve_ida(subtree),
+        SearchMode::Beam { width } => solver.solve_beam(subtree, width),
+    }
+
+    let status = solver.status();
+    let stats = solver.stats();
+    let best = solver.best_solution.map(|moves| {
+        let solution = Solution::from_moves(&moves);
+        (solution, solver.best_cost)
+    });
+
+    SolveOutcome {
+        best,
+        status,
+        stats,
+    }
+}
+
+/// コストが小さい順に最大 `k` 個の解を求める (`SolverConfig::max_cost` 以下のもののみ)。
+///
+/// 異なる手順で同一局面に至る解も区別するため、置換表は使わない。
+/// また、並列探索には対応しない。
+/// 探索が打ち切られた場合、それまでに見つかった解を返す。
+pub fn solve_problem_top_k(arg: &SolverArgument, k: usize) -> Vec<(Solution, Cost)> {
+    let config = SolverConfig {
+        tt_capacity: 0,
+        ..arg.config.clone()
+    };
+    let mut solver = Solver::with_argument(arg, config);
+    solver.top_k = Some(BoundedHeap::new(k));
+
+    if k > 0 {
+        let root = Subtree::root(arg);
+        match arg.config.search_mode {
+            SearchMode::DepthFirst => solver.solve_subtree(&root),
+            SearchMode::IdaStar => solver.solve_ida(&root),
+            SearchMode::Beam { width } => solver.solve_beam(&root, width),
+        }
+    }
+
+    solver
+        .top_k
+        .unwrap()
+        .into_sorted_vec()
+        .into_iter()
+        .map(|elt| (Solution::from_moves(&elt.moves), elt.cost))
+        .collect()
+}
+
+/// コストが `max_cost` 以下の全ての解を列挙するイテレータを返す。
+///
+/// 解は深さ優先探索で見つかった順に遅延的に得られる (コスト順ではない)。
+/// 異なる手順で同一局面に至る解も区別するため、置換表は使わない。また、並列探索には対応しない。
+/// `SolverConfig::max_cost` は `max_cost` で置き換えられる。
+pub fn solutions_within(arg: &SolverArgument, max_cost: Cost) -> SolutionsWithin {
+    let config = SolverConfig {
+        max_cost,
+        tt_capacity: 0,
+        ..arg.config.clone()
+    };
+    let solver = Solver::with_argument(arg, config);
+
+    SolutionsWithin {
+        solver,
+        root: Some(Subtree::root(arg)),
+        stack: vec![],
+    }
+}
+
+/// `solutions_within()` が返すイテレータ。
+#[derive(Debug)]
+pub struct SolutionsWithin {
+    solver: Solver,
+    /// まだ訪問していない根。
+    root: Option<Subtree>,
+    /// 探索中の局面のスタック。`solver.cur_solution` は根から最上段の局面までの手順となる。
+    stack: Vec<EnumFrame>,
+}
+
+/// `SolutionsWithin` が探索中の局面。
+#[derive(Debug)]
+struct EnumFrame {
+    pos: Position,
+    moves: Moves,
+    cost: Cost,
+    moves_legal: Moves,
+    /// 次に試す合法手のインデックス。
+    idx: usize,
+}
+
+impl SolutionsWithin {
+    /// 探索の終了状態を返す。打ち切り条件 (`SolverConfig::max_nodes` など) により列挙が途中で終わった場合、
+    /// `SolveStatus::Interrupted` となる。
+    pub fn status(&self) -> SolveStatus {
+        self.solver.status()
+    }
+
+    /// これまでの探索の統計情報を返す。
+    pub fn stats(&self) -> SolverStats {
+        self.solver.stats()
+    }
+
+    /// 現在の手順での局面を訪問する。解ならそのコストを返し、子局面を探索すべきならスタックに積む。
+    fn visit(
+        &mut self,
+        pos: Position,
+        moves: Moves,
+        cost: Cost,
+        cost_last_throw: Cost,
+    ) -> Option<Cost> {
+        let solver = &mut self.solver;
+        if solver.check_interrupted() {
+            return None;
+        }
+
+        let cost_solved = solver.calc_cost_solved(&pos, cost, cost_last_throw);
+        if cost_solved > solver.config.max_cost {
+            solver.stats.prune_bound_count += 1;
+            return None;
+        }
+
+        let moves_legal = pos.legal_moves(&moves);
+        if moves_legal.is_empty() {
+            if solver.is_solved(&pos) {
+                solver.stats.leaf_solved_count += 1;
+                return Some(cost_solved);
+            }
+            solver.stats.leaf_miss_count += 1;
+            return None;
+        }
+
+        if !solver.can_satisfy_constraints(&pos) {
+            solver.stats.prune_constraint_count += 1;
+            return None;
+        }
+        if solver
+            .calc_cost_lower_bound(&pos, &moves_legal, cost)
+            .is_none_or(|cost_lower_bound| cost_lower_bound > solver.config.max_cost)
+        {
+            solver.stats.prune_bound_count += 1;
+            return None;
+        }
+
+        self.stack.push(EnumFrame {
+            pos,
+            moves,
+            cost,
+            moves_legal,
+            idx: 0,
+        });
+
+        None
+    }
+}
+
+impl Iterator for SolutionsWithin {
+    type Item = (Solution, Cost);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            self.solver.cur_solution = root.solution;
+            if let Some(cost) = self.visit(root.pos, root.moves, root.cost, root.cost_last_throw) {
+                return Some((Solution::from_moves(&self.solver.cur_solution), cost));
+            }
+        }
+
+        while self.solver.interrupted.is_none() {
+            let frame = self.stack.last_mut()?;
+
+            // 全ての合法手を試したら親局面に戻る。
+            let Some(&mv) = frame.moves_legal.get(frame.idx) else {
+                self.stack.pop();
+                if !self.stack.is_empty() {
+                    unsafe { self.solver.cur_solution.remove_last_unchecked() }
+                }
+                continue;
+            };
+            frame.idx += 1;
+
+            let mut pos = frame.pos.clone();
+            let Some((child, _)) = self.solver.make_child(&mut pos, mv) else {
+                continue;
+            };
+            let moves_nxt = pos.update_moves(&frame.moves);
+            let cost_nxt = frame.cost + child.cost_hero_move + child.cost_throw;
+
+            let depth = self.stack.len();
+            self.solver.cur_solution.push(mv);
+            let res = self.visit(pos, moves_nxt, cost_nxt, child.cost_throw);
+            let solution = res.map(|_| Solution::from_moves(&self.solver.cur_solution));
+
+            // 子局面がスタックに積まれなかったなら手順を戻す。
+            if self.stack.len() == depth {
+                unsafe { self.solver.cur_solution.remove_last_unchecked() }
+            }
+
+            if let (Some(solution), Some(cost)) = (solution, res) {
+                return Some((solution, cost));
+            }
+        }
+
+        None
+    }
+}
+
+/// `solve_problem_pareto()` の探索結果。
+#[derive(Debug)]
+pub struct ParetoOutcome {
+    /// パレート最適な解とその評価指標。`metrics` の順での辞書式順序で並ぶ。
+    pub front: Vec<(Solution, SolutionMetrics)>,
+    pub status: SolveStatus,
+    /// 探索の統計情報。
+    pub stats: SolverStats,
+}
+
+impl ParetoOutcome {
+    /// 探索を完了したかどうかを返す。
+    pub fn is_completed(&self) -> bool {
+        self.status == SolveStatus::Completed
+    }
+}
+
+/// 評価指標 `metrics` についてパレート最適な解を全て求める。
+///
+/// 評価指標が全て等しい解は 1 つだけを返す。
+/// 先頭の解は `metrics` の順での辞書式順序における最適解となる。
+/// 解が満たすべき条件 (`SolverConfig::end_constraints`, 着手フィルタ, `SolverConfig::max_cost` など) は
+/// `solve_problem()` と同じ。
+/// 置換表は使わず、並列探索には対応しない。探索が打ち切られた場合、それまでに見つかった解のみから求めた結果を返す。
+pub fn solve_problem_pareto(arg: &SolverArgument, metrics: &[Metric]) -> ParetoOutcome {
+    let config = SolverConfig {
+        tt_capacity: 0,
+        ..arg.config.clone()
+    };
+    assert!(
+        !metrics.contains(&Metric::Score) || arg.score_model.is_some(),
+        "評価指標に得点を含めるには得点モデルの設定が必要"
+    );
+
+    let mut solver = Solver::with_argument(arg, config);
+    solver.pareto_metrics = metrics.to_vec();
+
+    let mut root = Subtree::root(arg);
+    solver.cur_solution.clone_from(&root.solution);
+    solver.solve_pareto(&mut root.pos, &root.moves, root.cost, root.cost_last_throw);
+
+    let mut front = std::mem::take(&mut solver.pareto_front);
+    front.sort_by(|(_, lhs), (_, rhs)| {
+        metrics
+            .iter()
+            .map(|&metric| lhs.compare(rhs, metric))
+            .find(|ord| ord.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    ParetoOutcome {
+        front,
+        status: solver.status(),
+        stats: solver.stats(),
+    }
+}
+
+/// 残りブロック数が最小となる手順 (`solve_problem_fewest_blocks()` 用)。
+#[derive(Debug)]
+pub struct FewestBlocks {
+    pub solution: Solution,
+    /// 最終局面の残りブロック数。
+    pub block_count: u8,
+    /// 手順のコスト。最終局面が解けていれば `Solution::verify()` と同様に面クリアのコストなどを含み、
+    /// さもなくば単に各着手のコストの和となる。
+    pub cost: Cost,
+    /// 最終局面 (合法手がないか、残り手数が尽きた局面)。
+    pub position: Position,
+}
+
+/// `solve_problem_fewest_blocks()` の探索結果。
+#[derive(Debug)]
+pub struct FewestBlocksOutcome {
+    pub best: Option<FewestBlocks>,
+    pub status: SolveStatus,
+    /// 探索の統計情報。
+    pub stats: SolverStats,
+}
+
+impl FewestBlocksOutcome {
+    /// 探索を完了したかどうかを返す。
+    pub fn is_completed(&self) -> bool {
+        self.status == SolveStatus::Completed
+    }
+}
+
+/// 合法手 (あるいは残り手数) がなくなるまで着手した手順のうち、最終局面の残りブロック数が最小のもの
+/// (その中ではコストが最小のもの) を求める。
+///
+/// 面クリアできない問題について、どこまで解に近づけるかを調べるためのもの。
+/// 面クリアの可否は問わないため、`SolverConfig::forbid_just` と `SolverConfig::end_constraints` は無視する
+/// (着手フィルタは適用される)。
+/// コストによる枝刈りがほぼ効かないため、`solve_problem()` で解が見つかる問題に対しては遅い。
+/// また、並列探索には対応しない。
+pub fn solve_problem_fewest_blocks(arg: &SolverArgument) -> FewestBlocksOutcome {
+    let mut solver = Solver::with_argument(arg, arg.config.clone());
+
+    let mut root = Subtree::root(arg);
+    solver.cur_solution.clone_from(&root.solution);
+    solver.solve_fewest_blocks(&mut root.pos, &root.moves, root.cost, root.cost_last_throw);
+
+    FewestBlocksOutcome {
+        best: solver.fewest_blocks.take(),
+        status: solver.status(),
+        stats: solver.stats(),
+    }
+}
+
+/// コストのみで順序付けされた解 (`BoundedHeap` 用)。
+#[derive(Debug)]
+struct RankedSolution {
+    moves: Vec<Move>,
+    cost: Cost,
+}
+
+impl PartialEq for RankedSolution {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for RankedSolution {}
+
+impl PartialOrd for RankedSolution {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankedSolution {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.cost.cmp(&other.cost)
+    }
+}
+
+/// 並列探索において、タスク分割を行う深さ。
+#[cfg(feature = "parallel")]
+const PARALLEL_SPLIT_DEPTH: usize = 2;
+
+#[cfg(feature = "parallel")]
+type AtomicCost = std::sync::atomic::AtomicU16;
+
+/// `solve_problem()` の並列版。
+///
+/// 浅い深さで探索木をタスクに分割し、各スレッドがタスクを順に取って探索する。
+/// 最良コストは全スレッドで共有され、各スレッドはそれを用いて枝刈りを行う。
+#[cfg(feature = "parallel")]
+fn solve_problem_parallel(
+    arg: &SolverArgument,
+    config: &SolverConfig,
+    subtree: &Subtree,
+) -> SolveOutcome {
+    use std::sync::atomic::AtomicUsize;
+
+    let thread_count = config.thread_count;
+    let config_worker = SolverConfig {
+        tt_capacity: config.tt_capacity / thread_count,
+        ..config.clone()
+    };
+    // 探索ノード数は全スレッド、全反復で共有する。
+    let budget_worker = Budget {
+        node_count_shared: Some(SharedNodeCount::default()),
+        ..Budget::new(arg)
+    };
+
+    let best_cost_shared = Arc::new(AtomicCost::new(config.max_cost + 1));
+    let mut best: Option<(Vec<Move>, Cost)> = None;
+    let mut status = SolveStatus::Completed;
+    let mut stats = SolverStats::default();
+    let time_start = Instant::now();
+
+    let mut pass = |threshold: Cost| -> Cost {
+        // 浅い局面を探索してタスクを列挙する (この過程で解が見つかることもある)。
+        let mut splitter = Solver::with_argument(arg, config_worker.clone());
+        splitter.budget = budget_worker.clone();
+        splitter.best_cost_shared = Some(Arc::clone(&best_cost_shared));
+        splitter.split_depth = subtree.solution.len() + PARALLEL_SPLIT_DEPTH;
+        splitter.threshold = threshold;
+        splitter.solve_subtree(subtree);
+        splitter.budget.flush_node_count();
+
+        let tasks = std::mem::take(&mut splitter.tasks);
+        let mut results = vec![(
+            splitter.status(),
+            splitter.stats(),
+            splitter.best_solution,
+            splitter.best_cost,
+            splitter.threshold_next,
+        )];
+
+        let task_idx = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..thread_count)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut solver = Solver::with_argument(arg, config_worker.clone());
+                        solver.budget = budget_worker.clone();
+                        solver.best_cost_shared = Some(Arc::clone(&best_cost_shared));
+                        solver.threshold = threshold;
+                        while let Some(task) =
+                            tasks.get(task_idx.fetch_add(1, AtomicOrdering::Relaxed))
+                        {
+                            solver.solve_subtree(task);
+                        }
+                        solver.budget.flush_node_count();
+                        (
+                            solver.status(),
+                            solver.stats(),
+                            solver.best_solution,
+                            solver.best_cost,
+                            solver.threshold_next,
+                        )
+                    })
+                })
+                .collect();
+            results.extend(handles.into_iter().map(|handle| handle.join().unwrap()));
+        });
+
+        let mut threshold_next = COST_INF;
+        for (status_worker, stats_worker, solution, cost, threshold_next_worker) in results {
+            stats.merge(&stats_worker);
+            if let Some(solution) = solution {
+                if best.as_ref().is_none_or(|&(_, best_cost)| cost < best_cost) {
+                    best = Some((solution, cost));
+                }
+            }
+            threshold_next = threshold_next.min(threshold_next_worker);
+            if status == SolveStatus::Completed {
+                status = status_worker;
+            }
+        }
+
+        // 打ち切られたなら反復を終える。
+        if status != SolveStatus::Completed {
+            return COST_INF;
+        }
+
+        // 他スレッドが解を改善した後に記録された値は不要。
+        if threshold_next >= best_cost_shared.load(AtomicOrdering::Relaxed) {
+            COST_INF
+        } else {
+            threshold_next
+        }
+    };
+
+    match config.search_mode {
+        SearchMode::DepthFirst => {
+            pass(COST_INF);
+        }
+        SearchMode::IdaStar => {
+            // 面クリアできないことが明らかなら探索しない。
+            if let Some(threshold_init) = calc_subtree_lower_bound(
+                arg.cost_model(),
+                arg.game_mode,
+                subtree,
+                config.last_stage,
+            ) {
+                iterate_ida(threshold_init, pass);
+            }
+        }
+        SearchMode::Beam { .. } => unreachable!("ビームサーチは並列探索に対応しない"),
+    }
+
+    let best = best.map(|(moves, cost)| (Solution::from_moves(&moves), cost));
+    stats.elapsed = time_start.elapsed();
+
+    SolveOutcome {
+        best,
+        status,
+        stats,
+    }
+}
+
+/// 着手とその結果。
+#[derive(Debug)]
+struct Child {
+    mv: Move,
+    cost_hero_move: Cost,
+    cost_throw: Cost,
+    erase_count: u8,
+}
+
+#[derive(Debug)]
+struct Solver {
+    game_mode: GameMode,
+    config: SolverConfig,
+
+    best_solution: Option<Vec<Move>>,
+    /// 最良解のコスト。上位 k 個の解を求める場合、k 番目の解のコスト。
+    best_cost: Cost,
+    cur_solution: Vec<Move>,
+    /// 現在の手順の得点 (面クリアのボーナスを含まない。`solve_problem_pareto()` 用)。
+    cur_score: Score,
+
+    /// 上位 k 個の解を求める場合、それらを保持する。
+    top_k: Option<BoundedHeap<RankedSolution>>,
+
+    /// 残りブロック数が最小の手順 (`solve_problem_fewest_blocks()` 用)。
+    fewest_blocks: Option<FewestBlocks>,
+
+    /// パレート最適解を求める際の評価指標 (`solve_problem_pareto()` 用)。
+    pareto_metrics: Vec<Metric>,
+    /// これまでに見つかったパレート最適な解 (`solve_problem_pareto()` 用)。
+    pareto_front: Vec<(Solution, SolutionMetrics)>,
+
+    tt: TranspositionTable,
+
+    /// 深さごとの、各着手 (投げる行で識別) が解の改善に寄与した回数 (`MoveOrdering::History` 用)。
+    history: Vec<[u32; GroundRow::NUM]>,
+
+    budget: Budget,
+    /// 探索が打ち切られた場合、その理由。
+    interrupted: Option<InterruptReason>,
+
+    /// f 値 (コスト + 残りコストの下界) がこれを超える局面は探索しない (IDA* 用)。
+    threshold: Cost,
+    /// 閾値を超えたために探索しなかった局面の f 値の最小値 (IDA* 用)。
+    threshold_next: Cost,
+
+    observer: Option<ObserverRef>,
+    move_filters: Vec<MoveFilterRef>,
+    cost_model: CostModelRef,
+    score_model: Option<ScoreModelRef>,
+    stats: SolverStats,
+    time_start: Instant,
+
+    /// 全スレッドで共有する最良コスト (並列探索用)。
+    #[cfg(feature = "parallel")]
+    best_cost_shared: Option<Arc<AtomicCost>>,
+    /// この深さに達した局面は探索せず、タスクとして記録する (並列探索のタスク分割用)。
+    #[cfg(feature = "parallel")]
+    split_depth: usize,
+    #[cfg(feature = "parallel")]
+    tasks: Vec<Subtree>,
+}
+
+/// 探索の打ち切り条件。
+#[derive(Clone, Debug)]
+struct Budget {
+    node_count_max: u64,
+    /// 全スレッドで共有する探索ノード数 (並列探索用)。
+    /// `None` ならソルバー自身の探索ノード数で打ち切りを判定する。
+    #[cfg(feature = "parallel")]
+    node_count_shared: Option<SharedNodeCount>,
+    deadline: Option<Instant>,
+    cancel_token: Option<CancelToken>,
+}
+
+impl Budget {
+    fn new(arg: &SolverArgument) -> Self {
+        Self {
+            node_count_max: arg.config.max_nodes,
+            #[cfg(feature = "parallel")]
+            node_count_shared: None,
+            deadline: arg.config.time_limit.map(|limit| Instant::now() + limit),
+            cancel_token: arg.cancel_token.clone(),
+        }
+    }
+
+    fn unlimited() -> Self {
+        Self {
+            node_count_max: u64::MAX,
+            #[cfg(feature = "parallel")]
+            node_count_shared: None,
+            deadline: None,
+            cancel_token: None,
+        }
+    }
+
+    /// ノードを 1 個探索したことを記録し、探索ノード数の上限を超えたかどうかを返す。
+    /// `node_count` はソルバー自身の探索ノード数 (今回の分を含む)。
+    fn count_node(&mut self, node_count: u64) -> bool {
+        #[cfg(feature = "parallel")]
+        if let Some(shared) = &mut self.node_count_shared {
+            return shared.increment() > self.node_count_max;
+        }
+
+        node_count > self.node_count_max
+    }
+
+    /// 共有の探索ノード数に未反映の分を反映する (並列探索で、ソルバーが探索を終える際に呼ぶ)。
+    #[cfg(feature = "parallel")]
+    fn flush_node_count(&mut self) {
+        if let Some(shared) = &mut self.node_count_shared {
+            shared.flush();
+        }
+    }
+}
+
+/// 全スレッドで共有する探索ノード数。
+///
+/// 共有カウンタの更新は `NODE_COUNT_SHARE_INTERVAL` ノードごとにまとめて行うので、
+/// 全体の探索ノード数は上限を (スレッド数) × `NODE_COUNT_SHARE_INTERVAL` 未満だけ超えることがある。
+#[cfg(feature = "parallel")]
+#[derive(Clone, Debug, Default)]
+struct SharedNodeCount {
+    total: Arc<std::sync::atomic::AtomicU64>,
+    /// 共有カウンタに未反映のノード数。
+    pending: u64,
+    /// 最後に共有カウンタを更新した時点での値。
+    seen: u64,
+}
+
+#[cfg(feature = "parallel")]
+impl SharedNodeCount {
+    /// ノード数を 1 増やし、全体の探索ノード数の (このスレッドから見た) 値を返す。
+    fn increment(&mut self) -> u64 {
+        self.pending += 1;
+        if self.pending == NODE_COUNT_SHARE_INTERVAL {
+            self.flush();
+        }
+
+        self.seen + self.pending
+    }
+
+    fn flush(&mut self) {
+        self.seen = self.total.fetch_add(self.pending, AtomicOrdering::Relaxed) + self.pending;
+        self.pending = 0;
+    }
+}
+
+/// 並列探索において、共有の探索ノード数はノードをこの個数探索するごとに更新する。
+#[cfg(feature = "parallel")]
+const NODE_COUNT_SHARE_INTERVAL: u64 = 1 << 8;
+
+/// 探索時間の上限と中断要求は、ノードをこの個数探索するごとにチェックする。
+const INTERRUPT_CHECK_INTERVAL: u64 = 1 << 12;
+
+/// ノードをこの個数探索するごとに `SolverObserver::on_progress()` を呼ぶ。
+const PROGRESS_INTERVAL: u64 = 1 << 16;
+
+/// ビームサーチの評価値において、目標ブロック数を超えているブロック 1 個あたりのペナルティ。
+const BEAM_COST_PER_BLOCK: Cost = 64;
+
+/// `explain_solution()` の各探索で用いる置換表のエントリ数の上限。
+const EXPLAIN_TT_CAPACITY: usize = 1 << 16;
+
+/// IDA* において、1 回の反復で閾値を上げる幅の最小値。
+/// 閾値を小刻みに上げると反復回数が増えすぎるため。
+const IDA_THRESHOLD_STEP_MIN: Cost = 64;
+
+/// 部分木の根を経由して面クリアするまでにかかるコストの下界を求める (IDA* の閾値の初期値)。
+/// 面クリアできないことが明らかな場合、`None` を返す。
+fn calc_subtree_lower_bound(
+    cost_model: &dyn CostModel,
+    game_mode: GameMode,
+    subtree: &Subtree,
+    last_stage: bool,
+) -> Option<Cost> {
+    let moves_legal = subtree.pos.legal_moves(&subtree.moves);
+    if moves_legal.is_empty() {
+        Some(subtree.cost)
+    } else {
+        calc_remaining_cost_lower_bound(
+            cost_model,
+            game_mode,
+            &subtree.pos,
+            &moves_legal,
+            last_stage,
+        )
+        .map(|cost| subtree.cost.saturating_add(cost))
+    }
+}
+
+/// IDA* の反復を行う。
+/// `pass` は閾値を受け取って探索を 1 回行い、閾値を超えたために探索しなかった局面の f 値の最小値を返す。
+fn iterate_ida(threshold_init: Cost, mut pass: impl FnMut(Cost) -> Cost) {
+    let mut threshold = threshold_init;
+
+    loop {
+        debug!("IDA* threshold: {threshold}");
+
+        let threshold_next = pass(threshold);
+
+        // 閾値を超えて探索しなかった局面がなくなるまで反復する。
+        // (解が改善しうる局面のみが記録されるので、最適性の証明も兼ねる)
+        if threshold_next == COST_INF {
+            break;
+        }
+        threshold = threshold_next.max(threshold + IDA_THRESHOLD_STEP_MIN);
+    }
+}
+
+impl Solver {
+    fn new(game_mode: GameMode, config: SolverConfig) -> Self {
+        let best_cost = config.max_cost + 1;
+        // ビームサーチは置換表を使わない。
+        let tt_capacity = if !matches!(config.search_mode, SearchMode::Beam { .. }) {
+            config.tt_capacity
+        } else {
+            0
+        };
+        let tt = TranspositionTable::new(tt_capacity);
+
+        Self {
+            game_mode,
+            config,
+
+            best_solution: None,
+            best_cost,
+            cur_solution: vec![],
+            cur_score: 0,
+
+            top_k: None,
+
+            fewest_blocks: None,
+
+            pareto_metrics: vec![],
+            pareto_front: vec![],
+
+            tt,
+
+            history: vec![],
+
+            budget: Budget::unlimited(),
+            interrupted: None,
+
+            threshold: COST_INF,
+            threshold_next: COST_INF,
+
+            observer: None,
+            move_filters: vec![],
+            cost_model: CostModelRef::default(),
+            score_model: None,
+            stats: SolverStats::default(),
+            time_start: Instant::now(),
+
+            #[cfg(feature = "parallel")]
+            best_cost_shared: None,
+            #[cfg(feature = "parallel")]
+            split_depth: usize::MAX,
+            #[cfg(feature = "parallel")]
+            tasks: vec![],
+        }
+    }
+
+    /// `arg` の打ち切り条件、オブザーバ、着手フィルタ、コストモデル、得点モデルを引き継いだソルバーを作る。
+    fn with_argument(arg: &SolverArgument, config: SolverConfig) -> Self {
+        let mut solver = Self::new(arg.game_mode, config);
+        solver.budget = Budget::new(arg);
+        solver.observer = arg.observer.clone();
+        solver.move_filters = arg.move_filters.clone();
+        solver.cost_model = arg.cost_model.clone();
+        solver.score_model = arg.score_model.clone();
+        solver
+    }
+
+    fn solve_ida(&mut self, subtree: &Subtree) {
+        // 面クリアできないことが明らかなら探索しない。
+        let Some(threshold_init) = calc_subtree_lower_bound(
+            self.cost_model.0.as_ref(),
+            self.game_mode,
+            subtree,
+            self.config.last_stage,
+        ) else {
+            return;
+        };
+
+        iterate_ida(threshold_init, |threshold| {
+            self.threshold = threshold;
+            self.threshold_next = COST_INF;
+            // 前回の反復で記録したコストによって枝刈りされないようにする。
+            self.tt.clear();
+
+            self.solve_subtree(subtree);
+
+            // 打ち切られたなら反復を終える。
+            if self.interrupted.is_some() {
+                return COST_INF;
+            }
+
+            self.threshold_next
+        });
+    }
+
+    /// 部分木 `subtree` 内をビームサーチする。
+    fn solve_beam(&mut self, subtree: &Subtree, width: usize) {
+        let mut beam = vec![subtree.clone()];
+
+        while !beam.is_empty() {
+            // (評価値, 局面キー, ノード)
+            let mut children = Vec::<(Cost, u128, Subtree)>::new();
+
+            for node in beam {
+                if self.check_interrupted() {
+                    return;
+                }
+
+                for child in self.expand_beam_node(node) {
+                    let moves_legal = child.pos.legal_moves(&child.moves);
+                    let Some(cost_lower_bound) = self
+                        .calc_cost_lower_bound(&child.pos, &moves_legal, child.cost)
+                        .filter(|&cost_lower_bound| cost_lower_bound < self.cost_bound())
+                    else {
+                        self.stats.prune_bound_count += 1;
+                        continue;
+                    };
+                    let score = self.calc_beam_score(&child, cost_lower_bound);
+                    children.push((score, child.pos.key(), child));
+                }
+            }
+
+            // 同一局面は最もコストが小さいもののみ残す。
+            children.sort_unstable_by_key(|(_, key, child)| (*key, child.cost));
+            children.dedup_by_key(|(_, key, _)| *key);
+
+            // 評価値の良いものを最大 width 個残す。
+            if children.len() > width {
+                children.select_nth_unstable_by_key(width, |(score, _, _)| *score);
+                children.truncate(width);
+            }
+
+            beam = children.into_iter().map(|(_, _, child)| child).collect();
+        }
+    }
+
+    /// ビームサーチのノードを展開し、合法手がある子ノードを返す。
+    /// 合法手がない局面に達したら、解けていれば解を更新する。
+    fn expand_beam_node(&mut self, node: Subtree) -> Vec<Subtree> {
+        self.cur_solution.clone_from(&node.solution);
+        self.stats.depth_max = self.stats.depth_max.max(node.solution.len());
+
+        let cost_solved = self.calc_cost_solved(&node.pos, node.cost, node.cost_last_throw);
+        if cost_solved >= self.cost_bound() {
+            self.stats.prune_bound_count += 1;
+            return vec![];
+        }
+
+        let moves_legal = node.pos.legal_moves(&node.moves);
+        if moves_legal.is_empty() {
+            if self.is_solved(&node.pos) {
+                self.stats.leaf_solved_count += 1;
+                self.update_best(cost_solved);
+            } else {
+                self.stats.leaf_miss_count += 1;
+            }
+            return vec![];
+        }
+
+        if !self.can_satisfy_constraints(&node.pos) {
+            self.stats.prune_constraint_count += 1;
+            return vec![];
+        }
+
+        // 面クリアできないことが明らかなら枝刈り (子ノードは追加時にチェック済みだが、根ノードはそうでない)。
+        if self
+            .calc_cost_lower_bound(&node.pos, &moves_legal, node.cost)
+            .is_none()
+        {
+            self.stats.prune_bound_count += 1;
+            return vec![];
+        }
+
+        let mut res = vec![];
+        for mv in moves_legal {
+            let mut pos = node.pos.clone();
+            let Some((child, _)) = self.make_child(&mut pos, mv) else {
+                continue;
+            };
+            let child = Subtree {
+                solution: [node.solution.as_slice(), &[mv]].concat(),
+                moves: pos.update_moves(&node.moves),
+                pos,
+                cost: node.cost + child.cost_hero_move + child.cost_throw,
+                cost_last_throw: child.cost_throw,
+            };
+
+            // 合法手がない子ノードはここで評価する。
+            if child.pos.legal_moves(&child.moves).is_empty() {
+                self.expand_beam_node(child);
+            } else {
+                res.push(child);
+            }
+        }
+
+        res
+    }
+
+    /// ビームサーチにおける、合法手があるノードの評価値 (小さいほど良い) を求める。
+    ///
+    /// 面クリアまでの総コストの下界に、目標ブロック数を超えている分のブロック数に応じたペナルティを加える。
+    /// (下界はブロック数をほとんど考慮しないので、これがないとブロックを消さない手順が残りやすい)
+    fn calc_beam_score(&self, node: &Subtree, cost_lower_bound: Cost) -> Cost {
+        let block_count_excess = node
+            .pos
+            .block_count()
+            .saturating_sub(self.game_mode.block_count_target());
+
+        cost_lower_bound.saturating_add(BEAM_COST_PER_BLOCK * Cost::from(block_count_excess))
+    }
+
+    /// 部分木 `subtree` を探索する。
+    fn solve_subtree(&mut self, subtree: &Subtree) {
+        self.cur_solution.clone_from(&subtree.solution);
+        self.solve(
+            &mut subtree.pos.clone(),
+            &subtree.moves,
+            subtree.cost,
+            subtree.cost_last_throw,
+        );
+    }
+
+    /// ノード数を数え、探索を打ち切るべきかどうかを返す。
+    fn check_interrupted(&mut self) -> bool {
+        if self.interrupted.is_some() {
+            return true;
+        }
+
+        self.stats.node_count += 1;
+
+        if let Some(observer) = &self.observer {
+            if self.stats.node_count.is_multiple_of(PROGRESS_INTERVAL) {
+                observer.0.on_progress(&self.stats());
+            }
+        }
+
+        if self.budget.count_node(self.stats.node_count) {
+            self.interrupted = Some(InterruptReason::NodeLimit);
+        } else if (self.stats.node_count - 1).is_multiple_of(INTERRUPT_CHECK_INTERVAL) {
+            // 最初のノードでもチェックするようにしている。
+            if self
+                .budget
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                self.interrupted = Some(InterruptReason::TimeLimit);
+            } else if self
+                .budget
+                .cancel_token
+                .as_ref()
+                .is_some_and(CancelToken::is_cancelled)
+            {
+                self.interrupted = Some(InterruptReason::Cancelled);
+            }
+        }
+
+        if let Some(reason) = self.interrupted {
+            debug!("interrupted: {reason:?}");
+        }
+
+        self.interrupted.is_some()
+    }
+
+    fn status(&self) -> SolveStatus {
+        self.interrupted
+            .map_or(SolveStatus::Completed, SolveStatus::Interrupted)
+    }
+
+    /// 現時点での統計情報を返す。
+    fn stats(&self) -> SolverStats {
+        SolverStats {
+            elapsed: self.time_start.elapsed(),
+            ..self.stats.clone()
+        }
+    }
+
+    /// 現在の手順を解として記録する。
+    fn update_best(&mut self, cost: Cost) {
+        if self.config.move_ordering == MoveOrdering::History {
+            if self.history.len() < self.cur_solution.len() {
+                self.history
+                    .resize(self.cur_solution.len(), [0; GroundRow::NUM]);
+            }
+            for (counts, mv) in self.history.iter_mut().zip(&self.cur_solution) {
+                counts[mv.src().to_index()] += 1;
+            }
+        }
+
+        if let Some(top_k) = &mut self.top_k {
+            // IDA* では反復ごとに同じ解が再び見つかるので、重複を除く。
+            if top_k.iter().any(|elt| elt.moves == self.cur_solution) {
+                return;
+            }
+            top_k.insert(RankedSolution {
+                moves: self.cur_solution.clone(),
+                cost,
+            });
+            // k 個の解が出揃ったら、以降は k 番目の解のコストで枝刈りできる。
+            if top_k.is_full() {
+                self.best_cost = top_k.peek().unwrap().cost;
+            }
+            let solution = Solution::from_moves(&self.cur_solution);
+            debug!("found: {cost} {solution}");
+            if let Some(observer) = &self.observer {
+                observer.0.on_improved(&solution, cost);
+            }
+            return;
+        }
+
+        self.best_solution = Some(self.cur_solution.clone());
+        self.best_cost = cost;
+        #[cfg(feature = "parallel")]
+        if let Some(shared) = &self.best_cost_shared {
+            shared.fetch_min(cost, AtomicOrdering::Relaxed);
+        }
+        let solution = Solution::from_moves(&self.cur_solution);
+        debug!("improved: {cost} {solution}");
+        if let Some(observer) = &self.observer {
+            observer.0.on_improved(&solution, cost);
+        }
+    }
+
+    /// 枝刈りに用いるコストの上限を返す。これ以上のコストの解は不要。
+    fn cost_bound(&self) -> Cost {
+        #[cfg(feature = "parallel")]
+        if let Some(shared) = &self.best_cost_shared {
+            return self.best_cost.min(shared.load(AtomicOrdering::Relaxed));
+        }
+
+        self.best_cost
+    }
+
+    /// `SolverConfig::move_ordering` に従い、子局面を試す順に並べ替える。
+    fn order_children(&self, children: &mut [Child]) {
+        let depth = self.cur_solution.len();
+
+        match self.config.move_ordering {
+            MoveOrdering::Static => {}
+            MoveOrdering::EraseCount => {
+                children.sort_by_key(|child| std::cmp::Reverse(child.erase_count));
+            }
+            MoveOrdering::HeroTravel => {
+                children.sort_by_key(|child| child.cost_hero_move);
+            }
+            MoveOrdering::History => {
+                let killer = self
+                    .best_solution
+                    .as_ref()
+                    .and_then(|solution| solution.get(depth))
+                    .map(|mv| mv.src());
+                let counts = self.history.get(depth);
+                children.sort_by_key(|child| {
+                    let src = child.mv.src();
+                    let count = counts.map_or(0, |counts| counts[src.to_index()]);
+                    std::cmp::Reverse((killer == Some(src), count))
+                });
+            }
+        }
+    }
+
+    /// 現局面 (現在の手順での局面) が解けていると仮定したときの総コストを求める。
+    fn calc_cost_solved(&self, pos: &Position, cost: Cost, cost_last_throw: Cost) -> Cost {
+        if self.config.last_stage {
+            // 最終面の場合、最終手のブロック投げコストは 1 (A を押して放置するだけ) とみなす。
+            // また、面クリア時の演出コストを加算しない。
+            cost - cost_last_throw + 1
+        } else {
+            // 最終面でない場合、面クリア時の演出コストを加算する。
+            cost + self.cost_model.0.clear_cost(
+                self.game_mode,
+                pos.block_count(),
+                self.config.last_stage,
+            )
+        }
+    }
+
+    /// 合法手がない局面 `pos` (現在の手順での局面) が解けているかどうかを返す。
+    /// just clear で、かつそれが禁止されているなら単にミスとみなす。
+    fn is_solved(&self, pos: &Position) -> bool {
+        let solved = if self.config.forbid_just {
+            pos.block_count() < self.game_mode.block_count_target()
+        } else {
+            pos.block_count() <= self.game_mode.block_count_target()
+        };
+
+        solved
+            && self
+                .config
+                .end_constraints
+                .is_satisfied(pos, self.cur_solution.len())
+    }
+
+    /// 同時消し数 `erase_count` の着手による得点を求める。得点モデルが設定されていなければ 0 とする。
+    fn throw_score(&self, erase_count: u8) -> Score {
+        self.score_model
+            .as_ref()
+            .map_or(0, |score_model| score_model.0.throw_score(erase_count))
+    }
+
+    /// 得点モデルを返す。得点を用いる探索でのみ呼ばれ、設定されていなければ panic する。
+    fn score_model(&self) -> &dyn ScoreModel {
+        self.score_model
+            .as_ref()
+            .expect("得点モデルが設定されていない")
+            .0
+            .as_ref()
+    }
+
+    /// 解けている局面 `pos` (現在の手順での局面) の得点を求める。
+    fn calc_score_solved(&self, pos: &Position) -> Score {
+        self.cur_score
+            + self.score_model().clear_score(
+                self.game_mode,
+                pos.block_count(),
+                pos.move_count_remain(),
+            )
+    }
+
+    /// 合法手がある局面 `pos` (現在の手順での局面) から得られる得点の上界を求める。
+    fn calc_score_upper_bound(&self, pos: &Position) -> Score {
+        let score_model = self.score_model();
+        let move_count_remain = pos.move_count_remain();
+        // 各着手で少なくとも 1 個のブロックが消えるので、残りブロック数も残り着手数の上界となる。
+        let move_count_max = move_count_remain.min(pos.block_count());
+
+        self.cur_score
+            + Score::from(move_count_max) * score_model.throw_score_max()
+            + score_model.clear_score_max(self.game_mode, move_count_remain.saturating_sub(1))
+    }
+
+    /// 合法手がある局面 `pos` (現在の手順での局面) から、制約を満たす解に到達しうるかどうかを返す。
+    fn can_satisfy_constraints(&self, pos: &Position) -> bool {
+        let constraints = &self.config.end_constraints;
+
+        constraints
+            .move_count_max
+            .is_none_or(|max| self.cur_solution.len() < usize::from(max))
+            && constraints.is_reachable(pos.block_count())
+    }
+
+    /// 合法手がある局面 `pos` にコスト `cost` で到達したとき、面クリアまでの総コストの下界を求める。
+    /// 面クリアできないことが明らかな場合 (残り手数が 0 の場合を含む)、`None` を返す。
+    fn calc_cost_lower_bound(
+        &self,
+        pos: &Position,
+        moves_legal: &[Move],
+        cost: Cost,
+    ) -> Option<Cost> {
+        calc_remaining_cost_lower_bound(
+            self.cost_model.0.as_ref(),
+            self.game_mode,
+            pos,
+            moves_legal,
+            self.config.last_stage,
+        )
+        .map(|cost_remain| cost.saturating_add(cost_remain))
+    }
+
+    fn solve(&mut self, pos: &mut Position, moves: &[Move], cost: Cost, cost_last_throw: Cost) {
+        if self.check_interrupted() {
+            return;
+        }
+
+        let depth = self.cur_solution.len();
+        if depth > self.stats.depth_max {
+            self.stats.depth_max = depth;
+            if let Some(observer) = &self.observer {
+                observer.0.on_depth_reached(depth);
+            }
+        }
+
+        // 現局面が解けていると仮定してもコストが改善しないなら枝刈り。
+        let cost_solved = self.calc_cost_solved(pos, cost, cost_last_throw);
+        if cost_solved >= self.cost_bound() {
+            self.stats.prune_bound_count += 1;
+            return;
+        }
+
+        // 合法手の集合を求める。
+        let moves_legal = pos.legal_moves(moves);
+
+        // 合法手がない場合、解けている/ミスのいずれかである。どちらにせよここで戻る。
+        // (just clear で、かつそれが禁止されているなら単にミスとみなす)
+        if moves_legal.is_empty() {
+            // 解けているなら解を更新(コストが改善しないケースは事前に枝刈りされている)。
+            if self.is_solved(pos) {
+                self.stats.leaf_solved_count += 1;
+                self.update_best(cost_solved);
+            } else {
+                self.stats.leaf_miss_count += 1;
+            }
+            return;
+        }
+
+        // 以降の着手で制約を満たせないなら枝刈り。
+        if !self.can_satisfy_constraints(pos) {
+            self.stats.prune_constraint_count += 1;
+            return;
+        }
+
+        // 面クリアできないことが明らかか、残りコストの下界を加えてもコストが改善しないなら枝刈り。
+        // (残り手数が 0 の局面は必ずここで枝刈りされる)
+        let Some(cost_lower_bound) = self
+            .calc_cost_lower_bound(pos, &moves_legal, cost)
+            .filter(|&cost_lower_bound| cost_lower_bound < self.cost_bound())
+        else {
+            self.stats.prune_bound_count += 1;
+            return;
+        };
+
+        // IDA* の閾値を超えるなら、次の反復に回す。
+        if cost_lower_bound > self.threshold {
+            self.threshold_next = self.threshold_next.min(cost_lower_bound);
+            self.stats.prune_threshold_count += 1;
+            return;
+        }
+
+        // 同一局面に同等以下のコストで到達済みなら枝刈り。
+        // (合法手がある局面では、以降のコストは局面のみで決まる)
+        if !self.tt.update(pos.key(), cost) {
+            self.stats.prune_tt_count += 1;
+            return;
+        }
+
+        // タスク分割中なら、この局面をタスクとして記録して戻る。
+        #[cfg(feature = "parallel")]
+        if self.cur_solution.len() == self.split_depth {
+            self.tasks.push(Subtree {
+                solution: self.cur_solution.clone(),
+                pos: pos.clone(),
+                moves: moves.iter().copied().collect(),
+                cost,
+                cost_last_throw,
+            });
+            return;
+        }
+
+        // 順序を変えない場合、子局面を一度に生成しない (その方が速い)。
+        if self.config.move_ordering == MoveOrdering::Static {
+            for mv in moves_legal {
+                if let Some((child, undo)) = self.make_child(pos, mv) {
+                    self.solve_child(pos, child, moves, cost);
+                    pos.unmake_move(&undo);
+                }
+            }
+            return;
+        }
+
+        // 順序付けに必要な情報を得るため、各着手を一旦行ってすぐに戻す。
+        let mut children: ArrayVec<_, 12> = moves_legal
+            .into_iter()
+            .filter_map(|mv| {
+                let (child, undo) = self.make_child(pos, mv)?;
+                pos.unmake_move(&undo);
+                Some(child)
+            })
+            .collect();
+
+        self.order_children(&mut children);
+
+        for child in children {
+            let (undo, ..) = pos.make_move_with(child.mv, self.cost_model.0.as_ref());
+            self.solve_child(pos, child, moves, cost);
+            pos.unmake_move(&undo);
+        }
+    }
+
+    /// パレート最適な解を求める探索 (`solve_problem_pareto()` 用)。
+    fn solve_pareto(
+        &mut self,
+        pos: &mut Position,
+        moves: &[Move],
+        cost: Cost,
+        cost_last_throw: Cost,
+    ) {
+        if self.check_interrupted() {
+            return;
+        }
+
+        let depth = self.cur_solution.len();
+        if depth > self.stats.depth_max {
+            self.stats.depth_max = depth;
+            if let Some(observer) = &self.observer {
+                observer.0.on_depth_reached(depth);
+            }
+        }
+
+        let moves_legal = pos.legal_moves(moves);
+
+        // 合法手がなければ、解けていて既存の解に支配されないなら記録する。
+        if moves_legal.is_empty() {
+            if !self.is_solved(pos) {
+                self.stats.leaf_miss_count += 1;
+                return;
+            }
+            self.stats.leaf_solved_count += 1;
+
+            let cost_solved = self.calc_cost_solved(pos, cost, cost_last_throw);
+            if cost_solved > self.config.max_cost {
+                self.stats.prune_bound_count += 1;
+                return;
+            }
+            let metrics = SolutionMetrics {
+                cost: cost_solved,
+                move_count: depth,
+                block_count: pos.block_count(),
+                score: self
+                    .score_model
+                    .is_some()
+                    .then(|| self.calc_score_solved(pos)),
+            };
+            self.update_pareto_front(metrics);
+            return;
+        }
+
+        if !self.can_satisfy_constraints(pos) {
+            self.stats.prune_constraint_count += 1;
+            return;
+        }
+
+        let Some(cost_lower_bound) = self
+            .calc_cost_lower_bound(pos, &moves_legal, cost)
+            .filter(|&cost_lower_bound| cost_lower_bound <= self.config.max_cost)
+        else {
+            self.stats.prune_bound_count += 1;
+            return;
+        };
+
+        // 以降で得られる解の評価指標の楽観値が既存の解に支配されるなら枝刈り。
+        let metrics_optimistic = SolutionMetrics {
+            cost: cost_lower_bound,
+            move_count: depth + 1,
+            block_count: 0,
+            score: self
+                .score_model
+                .is_some()
+                .then(|| self.calc_score_upper_bound(pos)),
+        };
+        if self
+            .pareto_front
+            .iter()
+            .any(|(_, metrics)| metrics.weakly_dominates(&metrics_optimistic, &self.pareto_metrics))
+        {
+            self.stats.prune_bound_count += 1;
+            return;
+        }
+
+        for mv in moves_legal {
+            if let Some((child, undo)) = self.make_child(pos, mv) {
+                let moves_nxt = pos.update_moves(moves);
+                let cost_nxt = cost + child.cost_hero_move + child.cost_throw;
+                let score_throw = self.throw_score(child.erase_count);
+
+                self.cur_solution.push(child.mv);
+                self.cur_score += score_throw;
+                self.solve_pareto(pos, &moves_nxt, cost_nxt, child.cost_throw);
+                self.cur_score -= score_throw;
+                unsafe { self.cur_solution.remove_last_unchecked() }
+                pos.unmake_move(&undo);
+            }
+        }
+    }
+
+    /// 現在の手順 (評価指標 `metrics` の解) がパレート最適なら記録し、それに支配される解を取り除く。
+    fn update_pareto_front(&mut self, metrics: SolutionMetrics) {
+        let pareto_metrics = &self.pareto_metrics;
+        if self
+            .pareto_front
+            .iter()
+            .any(|(_, other)| other.weakly_dominates(&metrics, pareto_metrics))
+        {
+            return;
+        }
+
+        self.pareto_front
+            .retain(|(_, other)| !metrics.weakly_dominates(other, pareto_metrics));
+
+        let solution = Solution::from_moves(&self.cur_solution);
+        debug!("pareto: {metrics:?} {solution}");
+        if let Some(observer) = &self.observer {
+            observer.0.on_improved(&solution, metrics.cost);
+        }
+        self.pareto_front.push((solution, metrics));
+    }
+
+    /// 最終局面の残りブロック数を最小化する探索 (`solve_problem_fewest_blocks()` 用)。
+    fn solve_fewest_blocks(
+        &mut self,
+        pos: &mut Position,
+        moves: &[Move],
+        cost: Cost,
+        cost_last_throw: Cost,
+    ) {
+        if self.check_interrupted() {
+            return;
+        }
+
+        let depth = self.cur_solution.len();
+        if depth > self.stats.depth_max {
+            self.stats.depth_max = depth;
+            if let Some(observer) = &self.observer {
+                observer.0.on_depth_reached(depth);
+            }
+        }
+
+        let moves_legal = pos.legal_moves(moves);
+
+        // 合法手がないか残り手数が尽きたなら、(残りブロック数, コスト) が改善する場合に記録する。
+        if moves_legal.is_empty() || pos.move_count_remain() == 0 {
+            let block_count = pos.block_count();
+            let cost =
+                if moves_legal.is_empty() && block_count <= self.game_mode.block_count_target() {
+                    self.stats.leaf_solved_count += 1;
+                    self.calc_cost_solved(pos, cost, cost_last_throw)
+                } else {
+                    self.stats.leaf_miss_count += 1;
+                    cost
+                };
+            let improved = self
+                .fewest_blocks
+                .as_ref()
+                .is_none_or(|best| (block_count, cost) < (best.block_count, best.cost));
+            if improved {
+                let solution = Solution::from_moves(&self.cur_solution);
+                debug!("improved: {block_count} blocks, {cost} {solution}");
+                self.fewest_blocks = Some(FewestBlocks {
+                    solution,
+                    block_count,
+                    cost,
+                    position: pos.clone(),
+                });
+            }
+            return;
+        }
+
+        // 同一局面に同等以下のコストで到達済みなら枝刈り。
+        // (合法手がある局面では、以降の残りブロック数とコストは局面のみで決まる)
+        if !self.tt.update(pos.key(), cost) {
+            self.stats.prune_tt_count += 1;
+            return;
+        }
+
+        for mv in moves_legal {
+            if let Some((child, undo)) = self.make_child(pos, mv) {
+                let moves_nxt = pos.update_moves(moves);
+                let cost_nxt = cost + child.cost_hero_move + child.cost_throw;
+
+                self.cur_solution.push(child.mv);
+                self.solve_fewest_blocks(pos, &moves_nxt, cost_nxt, child.cost_throw);
+                unsafe { self.cur_solution.remove_last_unchecked() }
+                pos.unmake_move(&undo);
+            }
+        }
+    }
+
+    /// 局面 `pos` 上で着手をその場で行い、(子局面の情報, 取り消し情報) を返す。
+    /// その着手が着手フィルタにより禁止されている場合は着手を取り消して `None` を返す。
+    fn make_child(&mut self, pos: &mut Position, mv: Move) -> Option<(Child, PositionUndo)> {
+        // 着手フィルタには着手前の局面も渡す必要がある。
+        let pos_before = (!self.move_filters.is_empty()).then(|| pos.clone());
+
+        let (undo, cost_hero_move, cost_throw, erase_count, sq_last) =
+            pos.make_move_with(mv, self.cost_model.0.as_ref());
+        if let Some(pos_before) = &pos_before {
+            let ctx = MoveContext {
+                pos_before,
+                mv,
+                erase_count,
+                sq_last,
+                pos_after: pos,
+            };
+            if find_rejecting_filter(&self.move_filters, &ctx).is_some() {
+                self.stats.move_filtered_count += 1;
+                pos.unmake_move(&undo);
+                return None;
+            }
+        }
+
+        let child = Child {
+            mv,
+            cost_hero_move,
+            cost_throw,
+            erase_count,
+        };
+
+        Some((child, undo))
+    }
+
+    /// 着手 `child.mv` を行った後の局面 `pos` を探索する。
+    fn solve_child(&mut self, pos: &mut Position, child: Child, moves: &[Move], cost: Cost) {
+        let moves_nxt = pos.update_moves(moves);
+        let cost_nxt = cost + child.cost_hero_move + child.cost_throw;
+
+        self.cur_solution.push(child.mv);
+        self.solve(pos, &moves_nxt, cost_nxt, child.cost_throw);
+        unsafe { self.cur_solution.remove_last_unchecked() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::ground::GROUND_ROW_5;
+    use crate::move_::MoveDirection;
+    use crate::test_util::{advance_fixture, advance_fixtures, advance_problem, TestScoreModel};
+
+    use super::*;
+
+    fn check_solve_advance(config: &SolverConfig) {
+        for (name, problem, solution_expect) in advance_fixtures(13) {
+            let arg = SolverArgument::new(&problem, config);
+            let cost_expect = solution_expect.verify(&arg).unwrap();
+
+            let (solution, cost) = solve_problem(&arg).unwrap();
+            assert_eq!(cost, cost_expect, "{name}");
+            assert_eq!(solution.verify(&arg).unwrap(), cost, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_solve_advance() {
+        check_solve_advance(&SolverConfig::default());
+    }
+
+    #[test]
+    fn test_solve_advance_ida_star() {
+        check_solve_advance(&SolverConfig {
+            search_mode: SearchMode::IdaStar,
+            tt_capacity: 1 << 12,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_solve_advance_move_ordering() {
+        for move_ordering in [
+            MoveOrdering::EraseCount,
+            MoveOrdering::HeroTravel,
+            MoveOrdering::History,
+        ] {
+            check_solve_advance(&SolverConfig {
+                move_ordering,
+                ..Default::default()
+            });
+        }
+    }
+
+    #[test]
+    fn test_solve_end_constraints() {
+        let problem = advance_problem(0);
+        let arg = SolverArgument::new(&problem, &SolverConfig::default());
+        let (solution, cost) = solve_problem(&arg).unwrap();
+
+        let config = SolverConfig {
+            end_constraints: EndConstraints {
+                move_count_min: Some(solution.len() as u8 + 1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let arg = SolverArgument::new(&problem, &config);
+
+        // 制約なしの最適解は制約を満たさない。
+        let err = solution.verify(&arg).unwrap_err();
+        assert!(format!("{err:#}").contains("着手数が最小値を下回る"));
+
+        let (solution_constrained, cost_constrained) = solve_problem(&arg).unwrap();
+        assert!(solution_constrained.len() > solution.len());
+        assert!(cost_constrained >= cost);
+        assert_eq!(solution_constrained.verify(&arg).unwrap(), cost_constrained);
+    }
+
+    #[test]
+    fn test_solve_move_filter() {
+        use crate::ground::GROUND_ROW_12;
+
+        // 想定解が 12 行目から投げる手を含む問題。
+        let (problem, solution) = advance_fixture(1);
+        assert!(solution.srcs().contains(&GROUND_ROW_12));
+
+        // forbid_five とクロージャによる同等のフィルタは同じ結果になる。
+        let config = SolverConfig {
+            forbid_five: true,
+            ..Default::default()
+        };
+        let arg = SolverArgument::new(&problem, &config);
+        let arg_closure = SolverArgument::new(&problem, &SolverConfig::default())
+            .with_move_filter(Arc::new(|ctx: &MoveContext<'_>| ctx.erase_count < 5));
+        assert_eq!(
+            solve_problem(&arg).map(|(_, cost)| cost),
+            solve_problem(&arg_closure).map(|(_, cost)| cost)
+        );
+
+        // 12 行目から投げる手を禁止する。
+        let arg = SolverArgument::new(&problem, &SolverConfig::default()).with_move_filter(
+            Arc::new(|ctx: &MoveContext<'_>| ctx.mv.src() != GROUND_ROW_12),
+        );
+        let err = solution.verify(&arg).unwrap_err();
+        assert!(err.to_string().contains("着手フィルタ"));
+        if let Some((solution, cost)) = solve_problem(&arg) {
+            assert!(!solution.srcs().contains(&GROUND_ROW_12));
+            assert_eq!(solution.verify(&arg).unwrap(), cost);
+        }
+    }
+
+    #[test]
+    fn test_solve_cost_model() {
+        use crate::block::BlocksSquare;
+
+        /// 自機の移動が NES 版の 2 倍遅いと仮定したモデル。
+        struct SlowHero;
+
+        impl CostModel for SlowHero {
+            fn hero_move_cost(&self, from: GroundRow, to: GroundRow) -> Cost {
+                2 * NesCostModel.hero_move_cost(from, to)
+            }
+
+            fn try_throw_cost(&self, src: GroundRow, sq_last: BlocksSquare) -> Option<Cost> {
+                NesCostModel.try_throw_cost(src, sq_last)
+            }
+
+            fn clear_cost(&self, game_mode: GameMode, block_count: u8, last_stage: bool) -> Cost {
+                NesCostModel.clear_cost(game_mode, block_count, last_stage)
+            }
+        }
+
+        for src in GroundRow::all() {
+            assert_eq!(
+                SlowHero.throw_cost_min(src),
+                NesCostModel.throw_cost_min(src)
+            );
+        }
+
+        let (problem, solution) = advance_fixture(0);
+        let cost_nes = solution
+            .verify(&SolverArgument::new(&problem, &SolverConfig::default()))
+            .unwrap();
+
+        let arg = SolverArgument::new(&problem, &SolverConfig::default())
+            .with_cost_model(Arc::new(SlowHero));
+        let (solution, cost) = solve_problem(&arg).unwrap();
+        assert_eq!(solution.verify(&arg).unwrap(), cost);
+        assert!(cost >= cost_nes);
+    }
+
+    #[test]
+    fn test_move_notation() {
+        for (name, problem, solution) in advance_fixtures(255) {
+            let arg = SolverArgument::new(&problem, &SolverConfig::default());
+
+            let notation = solution.to_notation(&arg).unwrap();
+            let moves = parse_move_notation(&arg, &notation).unwrap();
+            assert_eq!(moves, solution.to_moves(&arg).unwrap(), "{name}");
+            assert_eq!(
+                Solution::from_moves(&moves).srcs(),
+                solution.srcs(),
+                "{name}"
+            );
+
+            // 投げる行のみの記法 (従来の解の形式) も解釈できる。
+            let moves = parse_move_notation(&arg, &solution.to_string()).unwrap();
+            assert_eq!(
+                Solution::from_moves(&moves).srcs(),
+                solution.srcs(),
+                "{name}"
+            );
+        }
+
+        // 00 の問題では、5-8 行目から投げたブロックはいずれも A 列に落ちて A3 に当たる。
+        let problem = advance_problem(0);
+        let pos = Position::new(problem.blocks().clone(), Block::Normal2, 13);
+        let arg = SolverArgument::from_position(
+            problem.ground(),
+            problem.game_mode(),
+            pos,
+            &SolverConfig::default(),
+        );
+
+        let moves = parse_move_notation(&arg, "r5vA3").unwrap();
+        assert_eq!(
+            moves,
+            [Move::new(
+                GROUND_ROW_5,
+                BlocksSquare::A3,
+                MoveDirection::Vertical
+            )]
+        );
+
+        let cases = [
+            (
+                "vA3",
+                "0 番目の着手が曖昧: 'vA3' (候補: r8vA3,r7vA3,r6vA3,r5vA3)",
+            ),
+            ("r5vA3,r5vA4", "1 番目の着手に該当する合法手がない: 'r5vA4'"),
+            ("r5vA3,r5<A4", "1 番目の着手記法が不正"),
+        ];
+        for (s, expect) in cases {
+            let err = parse_move_notation(&arg, s).unwrap_err();
+            assert_eq!(err.to_string(), expect, "{s}");
+        }
+    }
+
+    #[test]
+    fn test_solve_from_position() {
+        let (problem, solution) = advance_fixture(0);
+        let arg = SolverArgument::new(&problem, &SolverConfig::default());
+        let cost_total = solution.verify(&arg).unwrap();
+
+        // 想定解の最初の 2 手を指した局面から解く。
+        let mut pos = arg.position().clone();
+        let mut moves = arg.moves().clone();
+        let mut cost_prefix = 0;
+        for &src in &solution.srcs()[..2] {
+            let mv = moves.iter().copied().find(|mv| mv.src() == src).unwrap();
+            let (pos_nxt, cost_hero_move, cost_throw, _, _) = pos.do_move(mv);
+            pos = pos_nxt;
+            moves = pos.update_moves(&moves);
+            cost_prefix += cost_hero_move + cost_throw;
+        }
+
+        let arg = SolverArgument::from_position(
+            problem.ground(),
+            problem.game_mode(),
+            pos,
+            &SolverConfig::default(),
+        );
+        assert_eq!(arg.moves(), &moves);
+
+        let (solution_rest, cost_rest) = solve_problem(&arg).unwrap();
+        assert_eq!(solution_rest.verify(&arg).unwrap(), cost_rest);
+        assert_eq!(cost_prefix + cost_rest, cost_total);
+    }
+
+    /// 残り手数が 0 で合法手が残っている局面からは、どの探索でも解が得られない (panic しない)。
+    #[test]
+    fn test_solve_from_position_no_moves_remain() {
+        let problem = advance_problem(0);
+        let pos = Position::new(
+            problem.blocks().clone(),
+            problem.game_mode().block_holding(),
+            0,
+        );
+
+        let mut configs = vec![];
+        for search_mode in [
+            SearchMode::DepthFirst,
+            SearchMode::IdaStar,
+            SearchMode::Beam { width: 10 },
+        ] {
+            configs.push(SolverConfig {
+                search_mode,
+                ..Default::default()
+            });
+            #[cfg(feature = "parallel")]
+            configs.push(SolverConfig {
+                search_mode,
+                thread_count: 4,
+                ..Default::default()
+            });
+        }
+
+        for config in configs {
+            let arg = SolverArgument::from_position(
+                problem.ground(),
+                problem.game_mode(),
+                pos.clone(),
+                &config,
+            )
+            .with_score_model(Arc::new(TestScoreModel));
+            assert!(!arg.position().legal_moves(arg.moves()).is_empty());
+
+            let outcome = solve_problem_outcome(&arg);
+            assert!(outcome.is_completed(), "{config:?}");
+            assert!(outcome.best.is_none(), "{config:?}");
+            assert!(solve_problem_top_k(&arg, 5).is_empty(), "{config:?}");
+            assert_eq!(solutions_within(&arg, COST_INF).count(), 0, "{config:?}");
+            assert!(
+                solve_problem_pareto(&arg, &[Metric::Cost, Metric::Score])
+                    .front
+                    .is_empty(),
+                "{config:?}"
+            );
+
+            // 残りブロック数最小化では、何も指さない手順が得られる。
+            let best = solve_problem_fewest_blocks(&arg).best.unwrap();
+            assert!(best.solution.is_empty(), "{config:?}");
+            assert_eq!(best.block_count, pos.block_count(), "{config:?}");
+        }
+    }
+
+    #[test]
+    fn test_solve_after_prefix() {
+        let (problem, solution) = advance_fixture(0);
+        let arg = SolverArgument::new(&problem, &SolverConfig::default());
+        let cost_expect = solution.verify(&arg).unwrap();
+
+        let prefix = Solution(solution.srcs()[..2].to_vec());
+        let outcome = solve_problem_after_prefix(&arg, &prefix).unwrap();
+        let (solution_actual, cost_actual) = outcome.best.unwrap();
+        assert!(solution_actual.srcs().starts_with(prefix.srcs()));
+        assert_eq!(solution_actual.verify(&arg).unwrap(), cost_actual);
+        assert_eq!(cost_actual, cost_expect);
+
+        // 手順全体を与えた場合、それ自体が解となる。
+        let outcome = solve_problem_after_prefix(&arg, &solution).unwrap();
+        assert_eq!(outcome.best.unwrap().1, cost_expect);
+
+        // 不正な手順を与えた場合はエラー。
+        let prefix: Solution = "12,12,12,12,12,12,12,12,12,12,12,12,12,12".parse().unwrap();
+        assert!(solve_problem_after_prefix(&arg, &prefix).is_err());
+    }
+
+    #[test]
+    fn test_solve_advance_beam() {
+        let config_beam = SolverConfig {
+            search_mode: SearchMode::Beam { width: 100 },
+            ..Default::default()
+        };
+
+        for (name, problem, solution) in advance_fixtures(13) {
+            let arg = SolverArgument::new(&problem, &config_beam);
+            let cost_optimal = solution.verify(&arg).unwrap();
+
+            let (solution_beam, cost_beam) = solve_problem(&arg).unwrap();
+            assert_eq!(solution_beam.verify(&arg).unwrap(), cost_beam, "{name}");
+            assert!(cost_beam >= cost_optimal, "{name}");
+
+            // ビームサーチの解のコストを上限として厳密な探索を行う。
+            let config = SolverConfig {
+                max_cost: cost_beam,
+                ..Default::default()
+            };
+            let arg = SolverArgument::new(&problem, &config);
+            let (_, cost) = solve_problem(&arg).unwrap();
+            assert_eq!(cost, cost_optimal, "{name}");
+        }
+    }
+
+    /// 設定 `config` で `solve_problem_top_k()` を行い、結果を検証する。
+    /// 深さ優先探索の場合とコストの列が一致することも確かめる。
+    fn check_solve_advance_top_k(config: &SolverConfig) {
+        const K: usize = 5;
+
+        for (name, problem, _) in advance_fixtures(13) {
+            let arg = SolverArgument::new(&problem, config);
+            let (_, cost_best) = solve_problem(&arg).unwrap();
+
+            let solutions = solve_problem_top_k(&arg, K);
+            let costs_dfs: Vec<_> =
+                solve_problem_top_k(&SolverArgument::new(&problem, &SolverConfig::default()), K)
+                    .into_iter()
+                    .map(|(_, cost)| cost)
+                    .collect();
+            assert_eq!(
+                solutions.iter().map(|(_, cost)| *cost).collect::<Vec<_>>(),
+                costs_dfs,
+                "{name}"
+            );
+            assert!(!solutions.is_empty() && solutions.len() <= K, "{name}");
+            assert_eq!(solutions[0].1, cost_best, "{name}");
+            assert!(solutions.windows(2).all(|w| w[0].1 <= w[1].1), "{name}");
+            for (solution, cost) in &solutions {
+                assert_eq!(solution.verify(&arg).unwrap(), *cost, "{name}");
+            }
+            assert!(
+                solutions
+                    .iter()
+                    .map(|(solution, _)| solution.to_string())
+                    .all_unique(),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_solve_advance_top_k() {
+        for search_mode in [SearchMode::DepthFirst, SearchMode::IdaStar] {
+            check_solve_advance_top_k(&SolverConfig {
+                search_mode,
+                ..Default::default()
+            });
+        }
+    }
+
+    #[test]
+    fn test_solve_solutions_within() {
+        const MARGIN: Cost = 20;
+
+        for (name, problem, _) in advance_fixtures(13) {
+            let arg = SolverArgument::new(&problem, &SolverConfig::default());
+            let (_, cost_best) = solve_problem(&arg).unwrap();
+            let max_cost = cost_best + MARGIN;
+
+            let mut iter = solutions_within(&arg, max_cost);
+            let solutions: Vec<_> = iter.by_ref().collect();
+            assert_eq!(iter.status(), SolveStatus::Completed, "{name}");
+
+            for (solution, cost) in &solutions {
+                assert!(*cost <= max_cost, "{name}");
+                assert_eq!(solution.verify(&arg).unwrap(), *cost, "{name}");
+            }
+            assert_eq!(
+                solutions.iter().map(|(_, cost)| *cost).min(),
+                Some(cost_best),
+                "{name}"
+            );
+            assert!(
+                solutions
+                    .iter()
+                    .map(|(solution, _)| solution.to_string())
+                    .all_unique(),
+                "{name}"
+            );
+
+            // 上位 k 個の解のうちコストが上限以下のものは全て列挙されている。
+            let found: HashSet<_> = solutions
+                .iter()
+                .map(|(solution, _)| solution.to_string())
+                .collect();
+            for (solution, _) in solve_problem_top_k(&arg, 5)
+                .into_iter()
+                .filter(|(_, cost)| *cost <= max_cost)
+            {
+                assert!(found.contains(&solution.to_string()), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_fewest_blocks() {
+        for (name, problem, solution) in advance_fixtures(13) {
+            // 解ける問題では、最適解と同じコストで面クリアする手順が得られる。
+            let arg = SolverArgument::new(&problem, &SolverConfig::default());
+            let cost_optimal = solution.verify(&arg).unwrap();
+
+            let outcome = solve_problem_fewest_blocks(&arg);
+            assert!(outcome.is_completed(), "{name}");
+            let best = outcome.best.unwrap();
+            assert!(
+                best.block_count <= problem.game_mode().block_count_target(),
+                "{name}"
+            );
+            assert_eq!(best.cost, cost_optimal, "{name}");
+            assert_eq!(best.solution.verify(&arg).unwrap(), cost_optimal, "{name}");
+
+            // 残り手数を 1 にすると解けないが、手順自体は得られる。
+            let pos = Position::new(
+                arg.position().blocks().clone(),
+                arg.position().block_holding(),
+                1,
+            );
+            let arg = SolverArgument::from_position(
+                problem.ground(),
+                problem.game_mode(),
+                pos,
+                &SolverConfig::default(),
+            );
+            assert!(solve_problem(&arg).is_none(), "{name}");
+
+            let best = solve_problem_fewest_blocks(&arg).best.unwrap();
+            assert!(
+                best.block_count > problem.game_mode().block_count_target(),
+                "{name}"
+            );
+            assert_eq!(best.position.block_count(), best.block_count, "{name}");
+            assert_eq!(best.position.move_count_remain(), 0, "{name}");
+
+            // 1 手で最も多くのブロックを消す手順となる。
+            let pos = arg.position();
+            let moves = arg.moves();
+            let erase_count_max = pos
+                .legal_moves(moves)
+                .into_iter()
+                .map(|mv| pos.do_move(mv).3)
+                .max()
+                .unwrap();
+            assert_eq!(best.solution.len(), 1, "{name}");
+            let mv = moves
+                .iter()
+                .copied()
+                .find(|mv| mv.src() == best.solution.srcs()[0])
+                .unwrap();
+            let (pos_nxt, _, _, erase_count, _) = pos.do_move(mv);
+            assert_eq!(erase_count, erase_count_max, "{name}");
+            assert_eq!(pos_nxt, best.position, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_verify_score() {
+        let score_model: Arc<dyn ScoreModel> = Arc::new(TestScoreModel);
+
+        let (problem, solution) = advance_fixture(4);
+        let arg = SolverArgument::new(&problem, &SolverConfig::default())
+            .with_score_model(score_model.clone());
+
+        // 各着手の得点と面クリアのボーナスの和と一致する。
+        let mut pos = arg.position().clone();
+        let mut moves = arg.moves().clone();
+        let mut score_expect = 0;
+        for &src in solution.srcs() {
+            let mv = moves.iter().copied().find(|mv| mv.src() == src).unwrap();
+            let (pos_nxt, _, _, erase_count, _) = pos.do_move(mv);
+            score_expect += score_model.throw_score(erase_count);
+            pos = pos_nxt;
+            moves = pos.update_moves(&moves);
+        }
+        score_expect +=
+            score_model.clear_score(arg.game_mode(), pos.block_count(), pos.move_count_remain());
+        assert_eq!(solution.verify_score(&arg).unwrap(), score_expect);
+        assert_eq!(
+            solution.verify_metrics(&arg).unwrap().score,
+            Some(score_expect)
+        );
+
+        // 得点モデルが設定されていなければ、得点は求まらない。
+        let arg = SolverArgument::new(&problem, &SolverConfig::default());
+        assert_eq!(solution.verify_metrics(&arg).unwrap().score, None);
+        assert!(solution.verify_score(&arg).is_err());
+    }
+
+    #[test]
+    fn test_solve_pareto() {
+        const METRICS_LIST: [&[Metric]; 3] = [
+            &[Metric::Cost, Metric::MoveCount, Metric::BlockCount],
+            &[Metric::Score, Metric::Cost],
+            &[Metric::MoveCount],
+        ];
+
+        let score_model: Arc<dyn ScoreModel> = Arc::new(TestScoreModel);
+
+        for (name, problem, _) in advance_fixtures(13) {
+            // 全ての解を列挙できる問題のみを対象とする。
+            let config = SolverConfig {
+                max_nodes: 100_000,
+                ..Default::default()
+            };
+            let arg = SolverArgument::new(&problem, &config).with_score_model(score_model.clone());
+            let mut iter = solutions_within(&arg, COST_INF);
+            let metrics_all: Vec<_> = iter
+                .by_ref()
+                .map(|(solution, _)| solution.verify_metrics(&arg).unwrap())
+                .collect();
+            if iter.status() != SolveStatus::Completed {
+                continue;
+            }
+
+            let arg = SolverArgument::new(&problem, &SolverConfig::default())
+                .with_score_model(score_model.clone());
+            let (_, cost_best) = solve_problem(&arg).unwrap();
+
+            for metrics in METRICS_LIST {
+                let outcome = solve_problem_pareto(&arg, metrics);
+                assert!(outcome.is_completed(), "{name}");
+
+                // 全列挙から求めたパレート最適な評価指標の集合と一致する。
+                let mut front_expect: Vec<_> = metrics_all
+                    .iter()
+                    .filter(|lhs| !metrics_all.iter().any(|rhs| rhs.dominates(lhs, metrics)))
+                    .map(|m| {
+                        metrics
+                            .iter()
+                            .map(|&metric| metric_key(m, metric))
+                            .collect_vec()
+                    })
+                    .collect();
+                front_expect.sort_unstable();
+                front_expect.dedup();
+
+                let front_actual = outcome
+                    .front
+                    .iter()
+                    .map(|(solution, m)| {
+                        assert_eq!(solution.verify_metrics(&arg).unwrap(), *m, "{name}");
+                        metrics
+                            .iter()
+                            .map(|&metric| metric_key(m, metric))
+                            .collect_vec()
+                    })
+                    .collect_vec();
+                assert!(front_actual.windows(2).all(|w| w[0] < w[1]), "{name}");
+                assert_eq!(front_actual, front_expect, "{name}");
+
+                if metrics[0] == Metric::Cost {
+                    assert_eq!(outcome.front[0].1.cost, cost_best, "{name}");
+                }
+            }
+        }
+    }
+
+    /// 評価指標 `metric` の値を小さいほど良い値に変換する。
+    fn metric_key(metrics: &SolutionMetrics, metric: Metric) -> i64 {
+        match metric {
+            Metric::Cost => i64::from(metrics.cost),
+            Metric::MoveCount => metrics.move_count as i64,
+            Metric::BlockCount => i64::from(metrics.block_count),
+            Metric::Score => -i64::from(metrics.score.unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_explain_solution() {
+        let (problem, solution) = advance_fixture(0);
+        let arg = SolverArgument::new(&problem, &SolverConfig::default());
+        let cost_optimal = solution.verify(&arg).unwrap();
+
+        // 最適解の各着手の regret は 0。
+        let explanations = explain_solution(&arg, &solution).unwrap();
+        assert_eq!(explanations.len(), solution.len());
+        for explanation in &explanations {
+            assert!(explanation.is_exact);
+            assert_eq!(explanation.regret(), 0);
+            assert_eq!(explanation.cost_best, cost_optimal);
+            assert!(explanation
+                .alternatives
+                .iter()
+                .any(|&(src, _)| src == explanation.played));
+        }
+
+        // 最適でない解の regret の和は、最適解とのコスト差に等しい。
+        let (solution_worst, cost_worst) = solve_problem_top_k(&arg, 5).pop().unwrap();
+        let explanations = explain_solution(&arg, &solution_worst).unwrap();
+        let regret_total: Cost = explanations.iter().map(MoveExplanation::regret).sum();
+        assert_eq!(regret_total, cost_worst - cost_optimal);
+        assert_eq!(explanations[0].cost_best, cost_optimal);
+        assert_eq!(explanations.last().unwrap().cost_played, cost_worst);
+
+        // 不正な解はエラー。
+        let solution: Solution = "12".parse().unwrap();
+        assert!(explain_solution(&arg, &solution).is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_solve_interrupted_parallel() {
+        let problem = advance_problem(49);
+
+        // ノード数の上限は、全スレッド、全反復の合計に対するもの。
+        let config = SolverConfig {
+            search_mode: SearchMode::IdaStar,
+            thread_count: 4,
+            max_nodes: 1_000_000,
+            ..Default::default()
+        };
+        let arg = SolverArgument::new(&problem, &config);
+        let outcome = solve_problem_outcome(&arg);
+        assert_eq!(
+            outcome.status,
+            SolveStatus::Interrupted(InterruptReason::NodeLimit)
+        );
+        // 最後の反復の各ソルバー (タスク分割用を含む) の未反映分だけ超えうる。
+        assert!(outcome.stats.node_count <= config.max_nodes + 5 * NODE_COUNT_SHARE_INTERVAL);
+    }
+
+    #[test]
+    fn test_solve_interrupted() {
+        // 残り 22 手の問題。
+        let problem = advance_problem(25);
+
+        let config = SolverConfig {
+            max_nodes: 1,
+            ..Default::default()
+        };
+        let arg = SolverArgument::new(&problem, &config);
+        let outcome = solve_problem_outcome(&arg);
+        assert_eq!(
+            outcome.status,
+            SolveStatus::Interrupted(InterruptReason::NodeLimit)
+        );
+        assert!(outcome.best.is_none());
+
+        let cancel_token = CancelToken::new();
+        cancel_token.cancel();
+        let arg =
+            SolverArgument::new(&problem, &SolverConfig::default()).with_cancel_token(cancel_token);
+        let outcome = solve_problem_outcome(&arg);
+        assert_eq!(
+            outcome.status,
+            SolveStatus::Interrupted(InterruptReason::Cancelled)
+        );
+        if let Some((solution, cost)) = outcome.best {
+            assert_eq!(solution.verify(&arg).unwrap(), cost);
+        }
+    }
+
+    #[test]
+    fn test_solve_observer() {
+        use std::sync::Mutex;
+
+        #[derive(Debug, Default)]
+        struct Recorder {
+            improved: Mutex<Vec<Cost>>,
+            depth_max: Mutex<usize>,
+        }
+
+        impl SolverObserver for Recorder {
+            fn on_improved(&self, _solution: &Solution, cost: Cost) {
+                self.improved.lock().unwrap().push(cost);
+            }
+
+            fn on_depth_reached(&self, depth: usize) {
+                let mut depth_max = self.depth_max.lock().unwrap();
+                *depth_max = (*depth_max).max(depth);
+            }
+        }
+
+        let problem = advance_problem(0);
+        let recorder = Arc::new(Recorder::default());
+        let arg = SolverArgument::new(&problem, &SolverConfig::default())
+            .with_observer(Arc::clone(&recorder) as Arc<dyn SolverObserver>);
+        let outcome = solve_problem_outcome(&arg);
+        let (_, cost) = outcome.best.unwrap();
+
+        // 改善のたびにコストは真に減少し、最後のものが最適解のコストとなる。
+        let improved = recorder.improved.lock().unwrap();
+        assert!(improved.iter().tuple_windows().all(|(x, y)| x > y));
+        assert_eq!(improved.last(), Some(&cost));
+
+        let stats = &outcome.stats;
+        assert_eq!(stats.depth_max, *recorder.depth_max.lock().unwrap());
+        assert!(stats.leaf_solved_count >= improved.len() as u64);
+        assert!(
+            stats.node_count
+                > stats.prune_bound_count + stats.leaf_solved_count + stats.leaf_miss_count
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_solve_advance_parallel() {
+        for search_mode in [SearchMode::DepthFirst, SearchMode::IdaStar] {
+            check_solve_advance(&SolverConfig {
+                search_mode,
+                thread_count: 4,
+                ..Default::default()
+            });
+        }
+    }
 
-            self.cur_solution.push(mv);
-            self.solve(&pos_nxt, &moves_nxt, cost_nxt, cost_throw);
-            unsafe { self.cur_solution.remove_last_unchecked() }
+    /// `solve_problem_top_k()` は並列探索に対応しないが、スレッド数を指定しても正しく動作する。
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_solve_advance_top_k_parallel() {
+        for search_mode in [SearchMode::DepthFirst, SearchMode::IdaStar] {
+            check_solve_advance_top_k(&SolverConfig {
+                search_mode,
+                thread_count: 4,
+                ..Default::default()
+            });
         }
     }
 }
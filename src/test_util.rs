@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+
+use crate::cost::ERASE_COUNT_MAX;
+use crate::game_mode::GameMode;
+use crate::problem::Problem;
+use crate::score::{Score, ScoreModel};
+use crate::solver::Solution;
+
+fn advance_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("problem-advance")
+}
+
+/// `problem-advance/` 内の問題 `NN.in` を読み込む。
+pub(crate) fn advance_problem(stage: u8) -> Problem {
+    std::fs::read_to_string(advance_dir().join(format!("{stage:02}.in")))
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+/// `problem-advance/` 内の全問題を (名前, 問題) として列挙する。
+pub(crate) fn advance_problems() -> impl Iterator<Item = (String, Problem)> {
+    (0..50).map(|stage| (format!("{stage:02}"), advance_problem(stage)))
+}
+
+/// `problem-advance/` 内の問題 `NN.in` と想定解 `NN.out` を読み込む。
+pub(crate) fn advance_fixture(stage: u8) -> (Problem, Solution) {
+    let solution: Solution = std::fs::read_to_string(advance_dir().join(format!("{stage:02}.out")))
+        .unwrap()
+        .trim_end()
+        .parse()
+        .unwrap();
+
+    (advance_problem(stage), solution)
+}
+
+/// `problem-advance/` 内の問題 (残り手数が `move_count_max` 以下のもの) と想定解を (名前, 問題, 解) として列挙する。
+pub(crate) fn advance_fixtures(move_count_max: u8) -> Vec<(String, Problem, Solution)> {
+    (0..50)
+        .map(|stage| (format!("{stage:02}"), advance_fixture(stage)))
+        .filter(|(_, (problem, _))| problem.game_mode().move_count_remain() <= move_count_max)
+        .map(|(name, (problem, solution))| (name, problem, solution))
+        .collect()
+}
+
+/// テスト用の得点モデル。値は実機の得点とは無関係。
+///
+/// 同時消しの得点は同時消し数について指数的に増え、面クリアのボーナスは残りブロック数が少ないほど、
+/// (ADVANCE モードでは) 残り手数が多いほど大きい。
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct TestScoreModel;
+
+impl ScoreModel for TestScoreModel {
+    fn throw_score(&self, erase_count: u8) -> Score {
+        if erase_count == 0 {
+            0
+        } else {
+            100 << (erase_count - 1)
+        }
+    }
+
+    fn clear_score(&self, game_mode: GameMode, block_count: u8, move_count_remain: u8) -> Score {
+        let block_count_under = game_mode.block_count_target().saturating_sub(block_count);
+        let score_move = if game_mode.is_advance() {
+            500 * Score::from(move_count_remain)
+        } else {
+            0
+        };
+        let score_perfect = if block_count == 0 { 10000 } else { 0 };
+
+        1000 + 1000 * Score::from(block_count_under) + score_move + score_perfect
+    }
+
+    fn throw_score_max(&self) -> Score {
+        self.throw_score(ERASE_COUNT_MAX)
+    }
+}
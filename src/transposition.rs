@@ -0,0 +1,108 @@
+use crate::cost::Cost;
+
+/// 置換表。
+///
+/// 局面キー (`Position::key()`) ごとに、その局面に到達した時点での最小コストを記録する。
+/// 容量は固定で、インデックスが衝突した場合は単に上書きする。
+///
+/// 各エントリは記録時の世代を持ち、現在の世代のものだけを有効とする。
+/// 世代を進めることで、表全体を書き換えずに全エントリを消去できる。
+#[derive(Debug)]
+pub(crate) struct TranspositionTable {
+    keys: Vec<u128>,
+    costs: Vec<Cost>,
+    generations: Vec<u32>,
+    generation: u32,
+    mask: usize,
+}
+
+impl TranspositionTable {
+    /// 容量 `capacity` (2 の冪に切り上げられる) の置換表を作る。
+    /// `capacity` が 0 の場合、何も記録しない置換表を作る。
+    pub(crate) fn new(capacity: usize) -> Self {
+        let capacity = if capacity == 0 {
+            0
+        } else {
+            capacity.next_power_of_two()
+        };
+
+        // 世代 0 のエントリを空エントリとみなす。
+        Self {
+            keys: vec![0; capacity],
+            costs: vec![0; capacity],
+            generations: vec![0; capacity],
+            generation: 1,
+            mask: capacity.wrapping_sub(1),
+        }
+    }
+
+    /// キー `key` の局面にコスト `cost` で到達したことを記録する。
+    /// その局面に `cost` 以下のコストで到達済みだった場合、何もせず `false` を返す。
+    pub(crate) fn update(&mut self, key: u128, cost: Cost) -> bool {
+        if self.keys.is_empty() {
+            return true;
+        }
+
+        let idx = Self::index(key, self.mask);
+        if self.generations[idx] == self.generation
+            && self.keys[idx] == key
+            && self.costs[idx] <= cost
+        {
+            return false;
+        }
+
+        self.keys[idx] = key;
+        self.costs[idx] = cost;
+        self.generations[idx] = self.generation;
+
+        true
+    }
+
+    /// 全エントリを消去する。
+    pub(crate) fn clear(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+
+        // 世代が一周したら、古い世代のエントリが有効とみなされないよう実際に消去する。
+        if self.generation == 0 {
+            self.generations.fill(0);
+            self.generation = 1;
+        }
+    }
+
+    fn index(key: u128, mask: usize) -> usize {
+        let h = (key as u64) ^ ((key >> 64) as u64).rotate_left(29);
+        let h = h.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+
+        (h >> 32) as usize & mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update() {
+        let mut tt = TranspositionTable::new(100);
+        assert!(tt.update(1, 50));
+        assert!(!tt.update(1, 50));
+        assert!(!tt.update(1, 60));
+        assert!(tt.update(1, 40));
+        assert!(tt.update(2, 100));
+
+        tt.clear();
+        assert!(tt.update(1, 60));
+        assert!(!tt.update(1, 60));
+
+        // 世代が一周しても、古いエントリは有効にならない。
+        tt.generation = u32::MAX;
+        assert!(tt.update(3, 10));
+        tt.clear();
+        assert_eq!(tt.generation, 1);
+        assert!(tt.update(3, 20));
+
+        let mut tt = TranspositionTable::new(0);
+        assert!(tt.update(1, 50));
+        assert!(tt.update(1, 50));
+    }
+}